@@ -0,0 +1,297 @@
+//! Columnar input for `score_hits`.
+//!
+//! Deserialising thousands of hit objects dominates `score_hits` on large result sets. The
+//! columnar entry point takes typed arrays instead: scores in a `Float64Array`, and every string
+//! field as a span of one UTF-8 buffer that is read in place. Spans are addressed by index, so a
+//! caller can either lay out one span per hit and field, or intern repeated values (cities,
+//! states, companies) once and point several hits at the same span.
+//!
+//! The ranking is the same as `score_hits_page`, except that ids are numbers and tie-break
+//! numerically. Only the indices of the requested page come back, with `nextCursor` and `total`.
+
+use crate::error::{from_js, ScorerError};
+use crate::{page_range, rank_indices, score_fields, HitFields, ScoreConfig, ScoreCursor};
+use js_sys::Uint32Array;
+use wasm_bindgen::prelude::*;
+
+/// String fields per hit, in this order: city, state, country, headline, title, company.
+pub const COLUMNAR_FIELDS: usize = 6;
+
+/// Field reference meaning "no value".
+pub const MISSING_FIELD: u32 = u32::MAX;
+
+/// Hits as parallel columns. Hit `i` has score `scores[i]`; everything else is optional.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColumnarHits<'a> {
+    pub scores: &'a [f64],
+    /// UTF-8 text. Span `k` is `text[offsets[k]..offsets[k + 1]]`.
+    pub text: &'a [u8],
+    pub offsets: &'a [u32],
+    /// `COLUMNAR_FIELDS` span indices per hit, or [`MISSING_FIELD`]. When empty, field `f` of
+    /// hit `i` is span `i * COLUMNAR_FIELDS + f`. Empty spans count as missing either way.
+    pub fields: &'a [u32],
+    /// `lat, lng` pairs, NaN for an unknown position. Empty when no hit has coordinates.
+    pub coords: &'a [f64],
+    /// Numeric hit ids, used for `tie_break` and cursors. Defaults to the hit index.
+    pub ids: &'a [u32],
+}
+
+fn expect_len(argument: &str, actual: usize, expected: usize) -> Result<(), ScorerError> {
+    if actual == 0 || actual == expected {
+        return Ok(());
+    }
+    Err(ScorerError::invalid_payload(
+        argument,
+        format!("expected {expected} values, got {actual}"),
+    ))
+}
+
+impl<'a> ColumnarHits<'a> {
+    fn span(&self, text: &'a str, span: usize) -> Result<&'a str, ScorerError> {
+        let bounds = self.offsets.get(span).zip(self.offsets.get(span + 1));
+        let Some((&start, &end)) = bounds else {
+            return Err(ScorerError::invalid_payload_at(
+                "offsets",
+                format!("offsets[{}]", span + 1),
+                format!("span {span} has no end offset"),
+            ));
+        };
+        text.get(start as usize..end as usize).ok_or_else(|| {
+            ScorerError::invalid_payload_at(
+                "offsets",
+                format!("offsets[{span}]"),
+                format!(
+                    "{start}..{end} is not a character range of the {} byte text",
+                    text.len()
+                ),
+            )
+        })
+    }
+
+    fn hit_fields(&self) -> Result<Vec<HitFields<'a>>, ScorerError> {
+        let count = self.scores.len();
+        expect_len("fields", self.fields.len(), count * COLUMNAR_FIELDS)?;
+        expect_len("coords", self.coords.len(), count * 2)?;
+        expect_len("ids", self.ids.len(), count)?;
+        if self.fields.is_empty() {
+            expect_len("offsets", self.offsets.len(), count * COLUMNAR_FIELDS + 1)?;
+        }
+        let text = std::str::from_utf8(self.text)
+            .map_err(|err| ScorerError::invalid_payload("text", err))?;
+
+        let field = |hit: usize, field: usize| -> Result<Option<&'a str>, ScorerError> {
+            if self.offsets.is_empty() {
+                return Ok(None);
+            }
+            let slot = hit * COLUMNAR_FIELDS + field;
+            let span = match self.fields.get(slot) {
+                Some(&MISSING_FIELD) => return Ok(None),
+                Some(&span) => span as usize,
+                None => slot,
+            };
+            if span + 1 >= self.offsets.len() {
+                return Err(ScorerError::invalid_payload_at(
+                    "fields",
+                    format!("fields[{slot}]"),
+                    format!("span {span} is out of range"),
+                ));
+            }
+            Ok(Some(self.span(text, span)?).filter(|value| !value.is_empty()))
+        };
+        let coordinate = |index: usize| {
+            self.coords
+                .get(index)
+                .copied()
+                .filter(|value| value.is_finite())
+        };
+
+        (0..count)
+            .map(|hit| {
+                Ok(HitFields {
+                    score: self.scores[hit],
+                    city: field(hit, 0)?,
+                    state: field(hit, 1)?,
+                    country: field(hit, 2)?,
+                    headline: field(hit, 3)?,
+                    title: field(hit, 4)?,
+                    company: field(hit, 5)?,
+                    lat: coordinate(hit * 2),
+                    lng: coordinate(hit * 2 + 1),
+                    website: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// One page of columnar hits, like [`ScoredPage`](crate::ScoredPage) without the scores.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ColumnarPage {
+    /// Hit indices on the page, in ranked order.
+    #[wasm_bindgen(skip)]
+    pub indices: Vec<u32>,
+    /// Set when more hits follow this page; its id is the numeric id of the page's last hit.
+    #[wasm_bindgen(skip)]
+    pub next_cursor: Option<ScoreCursor>,
+    /// Number of hits ranked across all pages, after diversity caps.
+    #[wasm_bindgen(skip)]
+    pub total: usize,
+}
+
+#[wasm_bindgen]
+impl ColumnarPage {
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Uint32Array {
+        Uint32Array::from(self.indices.as_slice())
+    }
+
+    #[wasm_bindgen(getter = nextCursor)]
+    pub fn next_cursor(&self) -> Option<String> {
+        self.next_cursor.clone().map(String::from)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+}
+
+/// Ranks columnar hits like `score_hits_page_impl` and returns the hit indices of the requested
+/// page. Ids tie-break numerically. `config.explain` is ignored, and the `domain` diversity key
+/// never matches since there is no website column.
+pub fn score_hits_columnar_impl(
+    hits: &ColumnarHits,
+    config: &ScoreConfig,
+) -> Result<ColumnarPage, ScorerError> {
+    let fields = hits.hit_fields()?;
+    let ids: Vec<u32> = if hits.ids.is_empty() {
+        (0..fields.len() as u32).collect()
+    } else {
+        hits.ids.to_vec()
+    };
+    let cursor = match &config.cursor {
+        Some(cursor) => {
+            let id = cursor.id.parse::<u32>().map_err(|_| {
+                ScorerError::invalid_payload_at(
+                    "config",
+                    "config.cursor".to_string(),
+                    format!("cursor id {:?} is not a numeric hit id", cursor.id),
+                )
+            })?;
+            Some((cursor.score, id))
+        }
+        None => None,
+    };
+
+    let (scores, _) = score_fields(&fields, config);
+    let ranked = rank_indices(&scores, &ids, &fields, config);
+    let page = page_range(&ranked, &scores, &ids, cursor, config);
+    let next_cursor = (page.end < ranked.len() && !page.is_empty()).then(|| {
+        let last = ranked[page.end - 1];
+        ScoreCursor {
+            score: scores[last],
+            id: ids[last].to_string(),
+        }
+    });
+    Ok(ColumnarPage {
+        indices: ranked[page].iter().map(|&index| index as u32).collect(),
+        next_cursor,
+        total: ranked.len(),
+    })
+}
+
+/// `score_hits_page` over typed arrays; see [`ColumnarHits`] for the layout. Pass empty arrays
+/// for `fields`, `coords` or `ids` to leave them out. Returns `{ indices, nextCursor, total }`.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn score_hits_columnar(
+    scores: &[f64],
+    text: &[u8],
+    offsets: &[u32],
+    fields: &[u32],
+    coords: &[f64],
+    ids: &[u32],
+    config: JsValue,
+) -> Result<ColumnarPage, ScorerError> {
+    let config: ScoreConfig = from_js("config", config)?;
+    let hits = ColumnarHits {
+        scores,
+        text,
+        offsets,
+        fields,
+        coords,
+        ids,
+    };
+    score_hits_columnar_impl(&hits, &config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diversity::{DiversityConfig, DiversityKey};
+    use crate::TieBreak;
+    use std::collections::HashMap;
+
+    #[test]
+    fn ids_tie_break_numerically() {
+        let scores = [1.0; 3];
+        let ids = [10, 2, 1];
+        let hits = ColumnarHits {
+            scores: &scores,
+            ids: &ids,
+            ..Default::default()
+        };
+        let config = ScoreConfig {
+            tie_break: TieBreak::Id,
+            ..Default::default()
+        };
+        let page = score_hits_columnar_impl(&hits, &config).unwrap();
+        assert_eq!(page.indices, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn pages_follow_next_cursor() {
+        let scores = [3.0, 1.0, 2.0];
+        let hits = ColumnarHits {
+            scores: &scores,
+            ..Default::default()
+        };
+        let mut config = ScoreConfig {
+            limit: 2,
+            ..Default::default()
+        };
+        let first = score_hits_columnar_impl(&hits, &config).unwrap();
+        assert_eq!((first.indices.as_slice(), first.total), (&[0, 2][..], 3));
+
+        config.cursor = first.next_cursor;
+        let second = score_hits_columnar_impl(&hits, &config).unwrap();
+        assert_eq!(second.indices, vec![1]);
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[test]
+    fn total_leaves_out_capped_hits() {
+        // Only the company field (the last one) is set.
+        let mut fields = vec![MISSING_FIELD; 3 * COLUMNAR_FIELDS];
+        for (hit, span) in [0, 0, 1].into_iter().enumerate() {
+            fields[hit * COLUMNAR_FIELDS + 5] = span;
+        }
+        let hits = ColumnarHits {
+            scores: &[3.0, 2.0, 1.0],
+            text: b"acmeglobex",
+            offsets: &[0, 4, 10],
+            fields: &fields,
+            ..Default::default()
+        };
+        let config = ScoreConfig {
+            diversity: Some(DiversityConfig {
+                caps: HashMap::from([(DiversityKey::Company, 1)]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let page = score_hits_columnar_impl(&hits, &config).unwrap();
+        assert_eq!((page.indices, page.total), (vec![0, 2], 2));
+    }
+}
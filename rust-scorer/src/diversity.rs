@@ -0,0 +1,257 @@
+//! Maximal marginal relevance (MMR) reranking over categorical keys.
+//!
+//! Results are picked greedily by `lambda * relevance - (1 - lambda) * similarity`, where
+//! relevance is the item's score rescaled to 0..1 and similarity is the largest share of
+//! diversity keys (company, city, ...) it has in common with any item already picked. Per-key
+//! caps are hard: items past a cap are left out of the result. With `backfill` they are pushed
+//! behind every uncapped item instead, so a short result list still fills up.
+
+use crate::error::{from_js, ScorerError};
+use crate::text::{normalize_text, website_domain};
+use serde::Deserialize;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiversityKey {
+    Company,
+    City,
+    State,
+    /// Website host, see [`website_domain`].
+    Domain,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct DiversityConfig {
+    /// Keys that make two results similar. Each shared key counts equally.
+    pub keys: Vec<DiversityKey>,
+    /// 1.0 keeps pure score order, 0.0 maximises diversity.
+    pub lambda: f64,
+    /// At most this many results per key value, e.g. `{ "company": 3 }`.
+    pub caps: HashMap<DiversityKey, usize>,
+    /// Append items past a cap after the uncapped ones instead of dropping them.
+    pub backfill: bool,
+}
+
+impl Default for DiversityConfig {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new(),
+            lambda: 0.7,
+            caps: HashMap::new(),
+            backfill: false,
+        }
+    }
+}
+
+impl DiversityConfig {
+    /// Whether `key` is a similarity key or capped, i.e. whether callers need to supply it.
+    pub fn uses(&self, key: DiversityKey) -> bool {
+        self.keys.contains(&key) || self.caps.contains_key(&key)
+    }
+
+    /// `keys` followed by any capped key that is not also a similarity key.
+    fn tracked_keys(&self) -> Vec<DiversityKey> {
+        let mut tracked = self.keys.clone();
+        let mut capped: Vec<DiversityKey> = self
+            .caps
+            .keys()
+            .copied()
+            .filter(|key| !self.keys.contains(key))
+            .collect();
+        capped.sort();
+        tracked.extend(capped);
+        tracked
+    }
+}
+
+/// One result to rerank. `score` is only compared within the batch.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiversityItem {
+    pub score: f64,
+    pub company: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub website: Option<String>,
+}
+
+impl DiversityItem {
+    fn value(&self, key: DiversityKey) -> Option<String> {
+        let normalized = |value: &Option<String>| {
+            Some(normalize_text(value.as_deref()?)).filter(|value| !value.is_empty())
+        };
+        match key {
+            DiversityKey::Company => normalized(&self.company),
+            DiversityKey::City => normalized(&self.city),
+            DiversityKey::State => normalized(&self.state),
+            DiversityKey::Domain => self.website.as_deref().and_then(website_domain),
+        }
+    }
+}
+
+/// Returns indices into `items` in reranked order. Only the first `limit` positions are picked
+/// by MMR; the rest follow in input order. Items past a cap are dropped, or come last with
+/// `config.backfill`.
+pub fn mmr_rerank_impl(
+    items: &[DiversityItem],
+    config: &DiversityConfig,
+    limit: usize,
+) -> Vec<usize> {
+    let tracked = config.tracked_keys();
+    if items.is_empty() || tracked.is_empty() {
+        return (0..items.len()).collect();
+    }
+
+    let values: Vec<Vec<Option<String>>> = items
+        .iter()
+        .map(|item| tracked.iter().map(|&key| item.value(key)).collect())
+        .collect();
+    let caps: Vec<Option<usize>> = tracked
+        .iter()
+        .map(|key| config.caps.get(key).copied())
+        .collect();
+
+    let finite_scores = items
+        .iter()
+        .map(|item| item.score)
+        .filter(|score| score.is_finite());
+    let min_score = finite_scores.clone().fold(f64::INFINITY, f64::min);
+    let max_score = finite_scores.fold(f64::NEG_INFINITY, f64::max);
+    let relevance = |index: usize| {
+        let score = items[index].score;
+        if !score.is_finite() {
+            0.0
+        } else if max_score > min_score {
+            (score - min_score) / (max_score - min_score)
+        } else {
+            1.0
+        }
+    };
+
+    let lambda = config.lambda.clamp(0.0, 1.0);
+    let similarity_keys = config.keys.len();
+    let mut max_similarity = vec![0.0_f64; items.len()];
+    let mut counts: Vec<HashMap<&str, usize>> = vec![HashMap::new(); tracked.len()];
+    let is_capped = |index: usize, counts: &[HashMap<&str, usize>]| {
+        values[index]
+            .iter()
+            .zip(&caps)
+            .enumerate()
+            .any(|(k, (value, cap))| match (value, cap) {
+                (Some(value), Some(cap)) => {
+                    counts[k].get(value.as_str()).copied().unwrap_or(0) >= *cap
+                }
+                _ => false,
+            })
+    };
+
+    let mut remaining: Vec<usize> = (0..items.len()).collect();
+    let mut ordered: Vec<usize> = Vec::with_capacity(items.len());
+
+    while ordered.len() < limit && !remaining.is_empty() {
+        let mut best: Option<(usize, f64)> = None;
+        for (position, &index) in remaining.iter().enumerate() {
+            if is_capped(index, &counts) {
+                continue;
+            }
+            let mmr = lambda * relevance(index) - (1.0 - lambda) * max_similarity[index];
+            // Strict comparison keeps the earlier item on ties.
+            if best.is_none_or(|(_, best_mmr)| mmr > best_mmr) {
+                best = Some((position, mmr));
+            }
+        }
+        let Some((position, _)) = best else {
+            break;
+        };
+
+        let picked = remaining.remove(position);
+        ordered.push(picked);
+        for (k, value) in values[picked].iter().enumerate() {
+            if let Some(value) = value {
+                *counts[k].entry(value.as_str()).or_insert(0) += 1;
+            }
+        }
+        if similarity_keys > 0 {
+            for &index in &remaining {
+                let shared = values[index][..similarity_keys]
+                    .iter()
+                    .zip(&values[picked])
+                    .filter(|(value, picked_value)| value.is_some() && value == picked_value)
+                    .count();
+                let similarity = shared as f64 / similarity_keys as f64;
+                max_similarity[index] = max_similarity[index].max(similarity);
+            }
+        }
+    }
+
+    if config.backfill {
+        let (uncapped, capped): (Vec<usize>, Vec<usize>) = remaining
+            .into_iter()
+            .partition(|&index| !is_capped(index, &counts));
+        ordered.extend(uncapped);
+        ordered.extend(capped);
+        return ordered;
+    }
+    for index in remaining {
+        if is_capped(index, &counts) {
+            continue;
+        }
+        ordered.push(index);
+        for (k, value) in values[index].iter().enumerate() {
+            if let Some(value) = value {
+                *counts[k].entry(value.as_str()).or_insert(0) += 1;
+            }
+        }
+    }
+    ordered
+}
+
+/// Reranks `items` (`{ score, company?, city?, state?, website? }`) and returns their indices.
+#[wasm_bindgen]
+pub fn mmr_rerank(items: JsValue, config: JsValue, limit: usize) -> Result<Vec<u32>, ScorerError> {
+    let items: Vec<DiversityItem> = from_js("items", items)?;
+    let config: DiversityConfig = from_js("config", config)?;
+    Ok(mmr_rerank_impl(&items, &config, limit)
+        .into_iter()
+        .map(|index| index as u32)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(score: f64, company: &str) -> DiversityItem {
+        DiversityItem {
+            score,
+            company: Some(company.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn capped_config(backfill: bool) -> DiversityConfig {
+        DiversityConfig {
+            caps: HashMap::from([(DiversityKey::Company, 1)]),
+            backfill,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn caps_drop_items_past_the_cap() {
+        let items = [item(3.0, "acme"), item(2.0, "acme"), item(1.0, "globex")];
+        let config = capped_config(false);
+        assert_eq!(mmr_rerank_impl(&items, &config, 3), vec![0, 2]);
+        // Items beyond `limit` are capped too.
+        assert_eq!(mmr_rerank_impl(&items, &config, 1), vec![0, 2]);
+    }
+
+    #[test]
+    fn backfill_appends_capped_items() {
+        let items = [item(3.0, "acme"), item(2.0, "acme"), item(1.0, "globex")];
+        let config = capped_config(true);
+        assert_eq!(mmr_rerank_impl(&items, &config, 3), vec![0, 2, 1]);
+    }
+}
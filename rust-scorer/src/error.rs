@@ -0,0 +1,197 @@
+//! The error type behind every export.
+//!
+//! Native callers get a [`ScorerError`] they can match on. At the WASM boundary it becomes a JS
+//! `Error` whose `message` keeps the old prefixes ("invalid hits payload: ...", "invalid
+//! multipolygon payload: ...", "failed to serialize score output: ...") and which also carries
+//! `code`, `path` (e.g. `hits[3].score`, or `null`) and `cause`, so callers can branch on
+//! `code` instead of parsing the message. The argument names below are chosen to reproduce
+//! those prefixes, so they are not always the JS parameter names.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScorerError {
+    /// An argument did not have the expected shape.
+    InvalidPayload {
+        argument: String,
+        path: Option<String>,
+        cause: String,
+    },
+    /// A result could not be converted to a JS value.
+    Serialization { output: String, cause: String },
+}
+
+impl ScorerError {
+    pub fn invalid_payload(argument: &str, cause: impl fmt::Display) -> Self {
+        Self::InvalidPayload {
+            argument: argument.to_string(),
+            path: None,
+            cause: cause.to_string(),
+        }
+    }
+
+    /// Like [`invalid_payload`](Self::invalid_payload), pointing at `path` inside the argument.
+    pub fn invalid_payload_at(argument: &str, path: String, cause: impl fmt::Display) -> Self {
+        Self::InvalidPayload {
+            argument: argument.to_string(),
+            path: Some(path),
+            cause: cause.to_string(),
+        }
+    }
+
+    pub fn serialization(output: &str, cause: impl fmt::Display) -> Self {
+        Self::Serialization {
+            output: output.to_string(),
+            cause: cause.to_string(),
+        }
+    }
+
+    /// Stable, machine-readable identifier: `invalid_payload` or `serialization_failed`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidPayload { .. } => "invalid_payload",
+            Self::Serialization { .. } => "serialization_failed",
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::InvalidPayload { path, .. } => path.as_deref(),
+            Self::Serialization { .. } => None,
+        }
+    }
+
+    pub fn cause(&self) -> &str {
+        match self {
+            Self::InvalidPayload { cause, .. } | Self::Serialization { cause, .. } => cause,
+        }
+    }
+}
+
+impl fmt::Display for ScorerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPayload {
+                argument, cause, ..
+            } => write!(f, "invalid {argument} payload: {cause}"),
+            Self::Serialization { output, cause } => {
+                write!(f, "failed to serialize {output}: {cause}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScorerError {}
+
+impl From<ScorerError> for JsValue {
+    fn from(err: ScorerError) -> Self {
+        let js_err = js_sys::Error::new(&err.to_string());
+        let path = err.path().map_or(JsValue::NULL, JsValue::from_str);
+        let fields = [
+            ("code", JsValue::from_str(err.code())),
+            ("path", path),
+            ("cause", JsValue::from_str(err.cause())),
+        ];
+        for (key, value) in fields {
+            // Setting a plain data property on a fresh Error cannot fail.
+            let _ = js_sys::Reflect::set(&js_err, &JsValue::from_str(key), &value);
+        }
+        js_err.into()
+    }
+}
+
+/// Deserialises `value` for the argument called `argument`, recording where in the payload
+/// deserialisation failed.
+pub fn from_js<T: DeserializeOwned>(argument: &str, value: JsValue) -> Result<T, ScorerError> {
+    deserialize_argument(argument, serde_wasm_bindgen::Deserializer::from(value))
+}
+
+/// [`from_js`] for any deserializer.
+fn deserialize_argument<'de, T, D>(argument: &str, deserializer: D) -> Result<T, ScorerError>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        let path = match err.path().to_string() {
+            root if root == "." => argument.to_string(),
+            nested if nested.starts_with('[') => format!("{argument}{nested}"),
+            nested => format!("{argument}.{nested}"),
+        };
+        ScorerError::InvalidPayload {
+            argument: argument.to_string(),
+            path: Some(path),
+            cause: err.into_inner().to_string(),
+        }
+    })
+}
+
+pub fn to_js<T: Serialize + ?Sized>(output: &str, value: &T) -> Result<JsValue, ScorerError> {
+    serde_wasm_bindgen::to_value(value).map_err(|err| ScorerError::serialization(output, err))
+}
+
+/// Like [`to_js`], but `None` fields become `null` instead of `undefined`.
+pub fn to_js_with_nulls<T: Serialize + ?Sized>(
+    output: &str,
+    value: &T,
+) -> Result<JsValue, ScorerError> {
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_missing_as_null(true);
+    value
+        .serialize(&serializer)
+        .map_err(|err| ScorerError::serialization(output, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HitInput, ScoreConfig};
+    use serde::de::value::{Error, MapDeserializer};
+    use serde::de::IntoDeserializer;
+    use std::collections::BTreeMap;
+
+    fn parse_hits(hits: Vec<Vec<(&str, &str)>>) -> Result<Vec<HitInput>, ScorerError> {
+        let hits: Vec<BTreeMap<&str, &str>> = hits
+            .into_iter()
+            .map(|hit| hit.into_iter().collect())
+            .collect();
+        deserialize_argument("hits", IntoDeserializer::<Error>::into_deserializer(hits))
+    }
+
+    #[test]
+    fn invalid_payload_points_at_the_failing_field() {
+        let mut hits = vec![vec![("id", "a")]; 3];
+        hits.push(vec![("id", "d"), ("score", "high")]);
+        let err = parse_hits(hits).unwrap_err();
+        assert_eq!(err.code(), "invalid_payload");
+        assert_eq!(err.path(), Some("hits[3].score"));
+        assert!(
+            err.to_string().starts_with("invalid hits payload: "),
+            "{err}"
+        );
+        assert!(err.cause().contains("invalid type"), "{}", err.cause());
+
+        assert_eq!(parse_hits(vec![vec![("id", "a")]]).unwrap()[0].id, "a");
+    }
+
+    #[test]
+    fn paths_start_with_the_argument_name() {
+        let config = MapDeserializer::<_, Error>::new(vec![("limit", "ten")].into_iter());
+        let err = deserialize_argument::<ScoreConfig, _>("score config", config).unwrap_err();
+        assert_eq!(err.path(), Some("score config.limit"));
+
+        let count = IntoDeserializer::<Error>::into_deserializer("seven");
+        let err = deserialize_argument::<u32, _>("count", count).unwrap_err();
+        assert_eq!(err.path(), Some("count"));
+    }
+
+    #[test]
+    fn serialization_errors_have_no_path() {
+        let err = ScorerError::serialization("score output", "boom");
+        assert_eq!(err.code(), "serialization_failed");
+        assert_eq!(err.path(), None);
+        assert_eq!(err.to_string(), "failed to serialize score output: boom");
+    }
+}
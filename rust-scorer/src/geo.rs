@@ -1,29 +1,83 @@
-use ::geo::algorithm::bool_ops::unary_union;
+use crate::error::{from_js, to_js, ScorerError};
+use ::geo::algorithm::bool_ops::{unary_union, BooleanOps};
 use ::geo::orient::{Direction, Orient};
-use ::geo::{Coord, LineString, Polygon};
+use ::geo::{Contains, Coord, LineString, MultiPolygon, Polygon, SimplifyIdx, SimplifyVwIdx};
 use js_sys::{Float64Array, Uint32Array, Uint8Array};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use wasm_bindgen::prelude::*;
 
 const EARTH_RADIUS_KM: f64 = 6371.0;
 const MAX_MERCATOR_LAT: f64 = 85.0;
+/// World width in pixels at zoom 0, as in web map tiles.
+const TILE_SIZE: f64 = 256.0;
+
+// (name, USPS code, centroid lat, centroid lng), mirroring `src/constants/usStates.ts`.
+const US_STATE_CENTROIDS: &[(&str, &str, f64, f64)] = &[
+    ("Alabama", "AL", 32.806671, -86.79113),
+    ("Alaska", "AK", 61.370716, -152.404419),
+    ("Arizona", "AZ", 33.729759, -111.431221),
+    ("Arkansas", "AR", 34.969704, -92.373123),
+    ("California", "CA", 36.116203, -119.681564),
+    ("Colorado", "CO", 39.059811, -105.311104),
+    ("Connecticut", "CT", 41.597782, -72.755371),
+    ("Delaware", "DE", 39.318523, -75.507141),
+    ("Florida", "FL", 27.766279, -81.686783),
+    ("Georgia", "GA", 33.040619, -83.643074),
+    ("Hawaii", "HI", 21.094318, -157.498337),
+    ("Idaho", "ID", 44.240459, -114.478828),
+    ("Illinois", "IL", 40.349457, -88.986137),
+    ("Indiana", "IN", 39.849426, -86.258278),
+    ("Iowa", "IA", 42.011539, -93.210526),
+    ("Kansas", "KS", 38.5266, -96.726486),
+    ("Kentucky", "KY", 37.66814, -84.670067),
+    ("Louisiana", "LA", 31.169546, -91.867805),
+    ("Maine", "ME", 44.693947, -69.381927),
+    ("Maryland", "MD", 39.063946, -76.802101),
+    ("Massachusetts", "MA", 42.230171, -71.530106),
+    ("Michigan", "MI", 43.326618, -84.536095),
+    ("Minnesota", "MN", 45.694454, -93.900192),
+    ("Mississippi", "MS", 32.741646, -89.678696),
+    ("Missouri", "MO", 38.456085, -92.288368),
+    ("Montana", "MT", 46.921925, -110.454353),
+    ("Nebraska", "NE", 41.12537, -98.268082),
+    ("Nevada", "NV", 38.313515, -117.055374),
+    ("New Hampshire", "NH", 43.452492, -71.563896),
+    ("New Jersey", "NJ", 40.298904, -74.521011),
+    ("New Mexico", "NM", 34.840515, -106.248482),
+    ("New York", "NY", 42.165726, -74.948051),
+    ("North Carolina", "NC", 35.630066, -79.806419),
+    ("North Dakota", "ND", 47.528912, -99.784012),
+    ("Ohio", "OH", 40.388783, -82.764915),
+    ("Oklahoma", "OK", 35.565342, -96.928917),
+    ("Oregon", "OR", 44.572021, -122.070938),
+    ("Pennsylvania", "PA", 40.590752, -77.209755),
+    ("Rhode Island", "RI", 41.680893, -71.51178),
+    ("South Carolina", "SC", 33.856892, -80.945007),
+    ("South Dakota", "SD", 44.299782, -99.438828),
+    ("Tennessee", "TN", 35.747845, -86.692345),
+    ("Texas", "TX", 31.054487, -97.563461),
+    ("Utah", "UT", 40.150032, -111.862434),
+    ("Vermont", "VT", 44.045876, -72.710686),
+    ("Virginia", "VA", 37.769337, -78.169968),
+    ("Washington", "WA", 47.400902, -121.490494),
+    ("West Virginia", "WV", 38.491226, -80.954456),
+    ("Wisconsin", "WI", 44.268543, -89.616508),
+    ("Wyoming", "WY", 42.755966, -107.30249),
+];
 
 // polygon-clipping compatible geometry format (x/y = lng/lat in this project)
-type ClippingCoord = [f64; 2];
-type ClippingRing = Vec<ClippingCoord>;
-type ClippingPolygon = Vec<ClippingRing>;
-type ClippingMultiPolygon = Vec<ClippingPolygon>;
+pub type ClippingCoord = [f64; 2];
+pub type ClippingRing = Vec<ClippingCoord>;
+pub type ClippingPolygon = Vec<ClippingRing>;
+pub type ClippingMultiPolygon = Vec<ClippingPolygon>;
 
 #[inline]
 fn coord_eq(a: &ClippingCoord, b: &ClippingCoord) -> bool {
     a[0] == b[0] && a[1] == b[1]
 }
 
-fn to_js_error(prefix: &str, err: impl std::fmt::Display) -> JsValue {
-    JsValue::from_str(&format!("{prefix}: {err}"))
-}
-
 fn ring_abs_area(ring: &ClippingRing) -> f64 {
     if ring.len() < 3 {
         return 0.0;
@@ -156,8 +210,15 @@ fn fnv1a_u32s(values: &[u32]) -> u32 {
     h
 }
 
+/// FNV-1a over `seed` followed by the UTF-8 bytes of `value`; a seeded, platform-stable hash
+/// for ordering string ids.
 #[inline]
-fn haversine_km_impl(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+pub fn fnv1a_str(seed: u32, value: &str) -> u32 {
+    value.bytes().fold(fnv1a_u32s(&[seed]), fnv1a_step)
+}
+
+#[inline]
+pub fn haversine_km_impl(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
     let d_lat = to_rad(lat2 - lat1);
     let d_lng = to_rad(lng2 - lng1);
     let lat1_rad = to_rad(lat1);
@@ -171,8 +232,40 @@ fn haversine_km_impl(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
     2.0 * EARTH_RADIUS_KM * h_root.asin()
 }
 
+/// Resolves a full name, USPS code or alias ("N.Y.", "Calif.", "washington state") to an index
+/// into `US_STATE_CENTROIDS`.
+fn us_state_index(input: &str) -> Option<usize> {
+    let region = crate::regions::normalize_region(input)?;
+    if !region.id.starts_with("US-") {
+        return None;
+    }
+    US_STATE_CENTROIDS
+        .iter()
+        .position(|(_, code, _, _)| *code == region.code)
+}
+
+pub fn nearest_us_states_impl(state: &str, count: usize) -> Vec<&'static str> {
+    let Some(origin_idx) = us_state_index(state) else {
+        return Vec::new();
+    };
+    let (_, _, origin_lat, origin_lng) = US_STATE_CENTROIDS[origin_idx];
+
+    let mut ranked: Vec<(&'static str, f64)> = US_STATE_CENTROIDS
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != origin_idx)
+        .map(|(_, (name, _, lat, lng))| {
+            (*name, haversine_km_impl(origin_lat, origin_lng, *lat, *lng))
+        })
+        .collect();
+    // Stable sort keeps table order for equal distances, matching the TypeScript fallback.
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    ranked.truncate(count);
+    ranked.into_iter().map(|(name, _)| name).collect()
+}
+
 #[inline]
-fn lat_lng_to_world_pixel_impl(lat: f64, lng: f64, world_size: f64) -> (f64, f64) {
+pub fn lat_lng_to_world_pixel_impl(lat: f64, lng: f64, world_size: f64) -> (f64, f64) {
     let lat_clamped = clamp(lat, -MAX_MERCATOR_LAT, MAX_MERCATOR_LAT);
     let siny = to_rad(lat_clamped).sin();
     let x = ((lng + 180.0) / 360.0) * world_size;
@@ -181,7 +274,14 @@ fn lat_lng_to_world_pixel_impl(lat: f64, lng: f64, world_size: f64) -> (f64, f64
 }
 
 #[inline]
-fn distance_point_to_segment_sq_impl(px: f64, py: f64, ax: f64, ay: f64, bx: f64, by: f64) -> f64 {
+pub fn distance_point_to_segment_sq_impl(
+    px: f64,
+    py: f64,
+    ax: f64,
+    ay: f64,
+    bx: f64,
+    by: f64,
+) -> f64 {
     let abx = bx - ax;
     let aby = by - ay;
     let apx = px - ax;
@@ -198,7 +298,7 @@ fn distance_point_to_segment_sq_impl(px: f64, py: f64, ax: f64, ay: f64, bx: f64
     dx * dx + dy * dy
 }
 
-fn point_in_ring_impl(px: f64, py: f64, ring: &[f64]) -> bool {
+pub fn point_in_ring_impl(px: f64, py: f64, ring: &[f64]) -> bool {
     let points_len = ring.len() / 2;
     if points_len < 3 {
         return false;
@@ -334,9 +434,7 @@ pub fn point_in_ring(px: f64, py: f64, ring: &Float64Array) -> bool {
     point_in_ring_impl(px, py, &ring_data)
 }
 
-#[wasm_bindgen]
-pub fn batch_lat_lng_to_world_pixel(coords: &Float64Array, world_size: f64) -> Float64Array {
-    let input = coords.to_vec();
+pub fn batch_lat_lng_to_world_pixel_impl(input: &[f64], world_size: f64) -> Vec<f64> {
     let pair_count = input.len() / 2;
     let mut output = vec![0.0; pair_count * 2];
 
@@ -348,16 +446,16 @@ pub fn batch_lat_lng_to_world_pixel(coords: &Float64Array, world_size: f64) -> F
         output[idx * 2 + 1] = y;
     }
 
-    Float64Array::from(output.as_slice())
+    output
 }
 
 #[wasm_bindgen]
-pub fn batch_haversine_km(
-    origin_lat: f64,
-    origin_lng: f64,
-    targets: &Float64Array,
-) -> Float64Array {
-    let input = targets.to_vec();
+pub fn batch_lat_lng_to_world_pixel(coords: &Float64Array, world_size: f64) -> Float64Array {
+    let output = batch_lat_lng_to_world_pixel_impl(&coords.to_vec(), world_size);
+    Float64Array::from(output.as_slice())
+}
+
+pub fn batch_haversine_km_impl(origin_lat: f64, origin_lng: f64, input: &[f64]) -> Vec<f64> {
     let pair_count = input.len() / 2;
     let mut output = vec![0.0; pair_count];
 
@@ -367,16 +465,34 @@ pub fn batch_haversine_km(
         output[idx] = haversine_km_impl(origin_lat, origin_lng, lat, lng);
     }
 
+    output
+}
+
+#[wasm_bindgen]
+pub fn batch_haversine_km(
+    origin_lat: f64,
+    origin_lng: f64,
+    targets: &Float64Array,
+) -> Float64Array {
+    let output = batch_haversine_km_impl(origin_lat, origin_lng, &targets.to_vec());
     Float64Array::from(output.as_slice())
 }
 
 #[wasm_bindgen]
-pub fn is_point_near_segments(x: f64, y: f64, segments: &Float64Array, threshold_px: f64) -> bool {
-    let data = segments.to_vec();
+pub fn nearest_us_states(state_name: &str, count: usize) -> Vec<String> {
+    nearest_us_states_impl(state_name, count)
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// `data` is either `[ax, ay, bx, by, min_x, max_x, min_y, max_y]` chunks with precomputed
+/// bounds or plain `[ax, ay, bx, by]` segments.
+pub fn is_point_near_segments_impl(x: f64, y: f64, data: &[f64], threshold_px: f64) -> bool {
     let t = threshold_px.max(0.0);
     let t_sq = t * t;
 
-    if data.len() >= 8 && data.len() % 8 == 0 {
+    if data.len() >= 8 && data.len().is_multiple_of(8) {
         for chunk in data.chunks_exact(8) {
             let ax = chunk[0];
             let ay = chunk[1];
@@ -416,33 +532,32 @@ pub fn is_point_near_segments(x: f64, y: f64, segments: &Float64Array, threshold
 }
 
 #[wasm_bindgen]
-pub fn pick_non_overlapping_indices(
-    xy: &Float64Array,
-    priority_order: &Uint32Array,
-    in_locked_order: &Uint32Array,
-    out_locked_order: &Uint32Array,
-    in_locked_mask: &Uint8Array,
+pub fn is_point_near_segments(x: f64, y: f64, segments: &Float64Array, threshold_px: f64) -> bool {
+    is_point_near_segments_impl(x, y, &segments.to_vec(), threshold_px)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn pick_non_overlapping_indices_impl(
+    xy_data: &[f64],
+    priority: &[u32],
+    in_locked: &[u32],
+    out_locked: &[u32],
+    in_locked_mask_data: &[u8],
     max_primary_dots: usize,
     in_locked_share: f64,
     hard_cap_outside_by_in_locked: bool,
     min_separation_sq: f64,
     cell_size: f64,
-) -> Uint32Array {
+) -> Vec<u32> {
     if max_primary_dots == 0 {
-        return Uint32Array::new_with_length(0);
+        return Vec::new();
     }
 
-    let xy_data = xy.to_vec();
     let candidate_count = xy_data.len() / 2;
     if candidate_count == 0 {
-        return Uint32Array::new_with_length(0);
+        return Vec::new();
     }
 
-    let priority = priority_order.to_vec();
-    let in_locked = in_locked_order.to_vec();
-    let out_locked = out_locked_order.to_vec();
-    let in_locked_mask_data = in_locked_mask.to_vec();
-
     let normalized_cell_size = if cell_size.is_finite() && cell_size > 0.0 {
         cell_size
     } else {
@@ -462,14 +577,14 @@ pub fn pick_non_overlapping_indices(
 
     let mut priority_budget = max_primary_dots;
     pick_from_order(
-        &priority,
-        &xy_data,
+        priority,
+        xy_data,
         candidate_count,
         normalized_cell_size,
         normalized_min_separation_sq,
         max_primary_dots,
         &mut priority_budget,
-        &in_locked_mask_data,
+        in_locked_mask_data,
         &mut picked,
         &mut picked_set,
         &mut grid,
@@ -485,14 +600,14 @@ pub fn pick_non_overlapping_indices(
         let mut out_locked_budget = remaining_budget - in_locked_budget;
 
         pick_from_order(
-            &in_locked,
-            &xy_data,
+            in_locked,
+            xy_data,
             candidate_count,
             normalized_cell_size,
             normalized_min_separation_sq,
             max_primary_dots,
             &mut in_locked_budget,
-            &in_locked_mask_data,
+            in_locked_mask_data,
             &mut picked,
             &mut picked_set,
             &mut grid,
@@ -504,14 +619,14 @@ pub fn pick_non_overlapping_indices(
         }
 
         pick_from_order(
-            &out_locked,
-            &xy_data,
+            out_locked,
+            xy_data,
             candidate_count,
             normalized_cell_size,
             normalized_min_separation_sq,
             max_primary_dots,
             &mut out_locked_budget,
-            &in_locked_mask_data,
+            in_locked_mask_data,
             &mut picked,
             &mut picked_set,
             &mut grid,
@@ -519,37 +634,63 @@ pub fn pick_non_overlapping_indices(
         );
     }
 
-    Uint32Array::from(picked.as_slice())
+    picked
 }
 
 #[wasm_bindgen]
-pub fn stable_viewport_sample(
-    coords: &Float64Array,
-    ids: &Uint32Array,
+#[allow(clippy::too_many_arguments)]
+pub fn pick_non_overlapping_indices(
+    xy: &Float64Array,
+    priority_order: &Uint32Array,
+    in_locked_order: &Uint32Array,
+    out_locked_order: &Uint32Array,
+    in_locked_mask: &Uint8Array,
+    max_primary_dots: usize,
+    in_locked_share: f64,
+    hard_cap_outside_by_in_locked: bool,
+    min_separation_sq: f64,
+    cell_size: f64,
+) -> Uint32Array {
+    let picked = pick_non_overlapping_indices_impl(
+        &xy.to_vec(),
+        &priority_order.to_vec(),
+        &in_locked_order.to_vec(),
+        &out_locked_order.to_vec(),
+        &in_locked_mask.to_vec(),
+        max_primary_dots,
+        in_locked_share,
+        hard_cap_outside_by_in_locked,
+        min_separation_sq,
+        cell_size,
+    );
+    Uint32Array::from(picked.as_slice())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn stable_viewport_sample_impl(
+    coords_data: &[f64],
+    ids_data: &[u32],
     min_lat: f64,
     max_lat: f64,
     min_lng: f64,
     max_lng: f64,
     slots: usize,
     seed: u32,
-) -> Uint32Array {
+) -> Vec<u32> {
     if slots == 0 {
-        return Uint32Array::new_with_length(0);
+        return Vec::new();
     }
-
-    let coords_data = coords.to_vec();
-    let ids_data = ids.to_vec();
     let coord_pairs = coords_data.len() / 2;
     let n = ids_data.len().min(coord_pairs);
     if n == 0 {
-        return Uint32Array::new_with_length(0);
+        return Vec::new();
     }
     if n <= slots {
         let mut all: Vec<u32> = Vec::with_capacity(n);
         for i in 0..n {
             all.push(i as u32);
         }
-        return Uint32Array::from(all.as_slice());
+        return all;
     }
 
     let lat_span = max_lat - min_lat;
@@ -558,8 +699,8 @@ pub fn stable_viewport_sample(
         !lat_span.is_finite() || !lng_span.is_finite() || lat_span <= 0.0 || lng_span <= 0.0;
     if degenerate_bbox {
         let mut scored: Vec<(u32, u32)> = Vec::with_capacity(n);
-        for i in 0..n {
-            let score = fnv1a_u32s(&[seed, 0x636f6e74, ids_data[i]]); // "cont"
+        for (i, id) in ids_data.iter().take(n).enumerate() {
+            let score = fnv1a_u32s(&[seed, 0x636f6e74, *id]); // "cont"
             scored.push((score, i as u32));
         }
         scored.sort_by(|a, b| {
@@ -571,10 +712,10 @@ pub fn stable_viewport_sample(
         });
         let take = slots.min(scored.len());
         let mut out: Vec<u32> = Vec::with_capacity(take);
-        for i in 0..take {
-            out.push(scored[i].1);
+        for entry in scored.iter().take(take) {
+            out.push(entry.1);
         }
-        return Uint32Array::from(out.as_slice());
+        return out;
     }
 
     let grid_unclamped = ((slots as f64).sqrt() * 1.15).round() as i64;
@@ -586,8 +727,8 @@ pub fn stable_viewport_sample(
         !lat_step.is_finite() || !lng_step.is_finite() || lat_step <= 0.0 || lng_step <= 0.0;
     if degenerate_steps {
         let mut scored: Vec<(u32, u32)> = Vec::with_capacity(n);
-        for i in 0..n {
-            let score = fnv1a_u32s(&[seed, 0x636f6e74, ids_data[i]]); // "cont"
+        for (i, id) in ids_data.iter().take(n).enumerate() {
+            let score = fnv1a_u32s(&[seed, 0x636f6e74, *id]); // "cont"
             scored.push((score, i as u32));
         }
         scored.sort_by(|a, b| {
@@ -599,10 +740,10 @@ pub fn stable_viewport_sample(
         });
         let take = slots.min(scored.len());
         let mut out: Vec<u32> = Vec::with_capacity(take);
-        for i in 0..take {
-            out.push(scored[i].1);
+        for entry in scored.iter().take(take) {
+            out.push(entry.1);
         }
-        return Uint32Array::from(out.as_slice());
+        return out;
     }
 
     #[derive(Clone, Copy)]
@@ -616,7 +757,7 @@ pub fn stable_viewport_sample(
     let mut non_empty_cells: Vec<u32> = Vec::new();
     let mut cell_has_any: Vec<bool> = vec![false; cell_count];
 
-    for i in 0..n {
+    for (i, id) in ids_data.iter().take(n).enumerate() {
         let base = i * 2;
         let lat = coords_data[base];
         let lng = coords_data[base + 1];
@@ -652,7 +793,7 @@ pub fn stable_viewport_sample(
             non_empty_cells.push(cell_key);
         }
 
-        let score = fnv1a_u32s(&[seed, 0x636f6e74, ids_data[i]]); // "cont"
+        let score = fnv1a_u32s(&[seed, 0x636f6e74, *id]); // "cont"
         cell_items[cell_idx_usize].push(ScoredIdx {
             idx: i as u32,
             score,
@@ -660,7 +801,7 @@ pub fn stable_viewport_sample(
     }
 
     if non_empty_cells.is_empty() {
-        return Uint32Array::new_with_length(0);
+        return Vec::new();
     }
 
     for &cell_key in &non_empty_cells {
@@ -710,13 +851,13 @@ pub fn stable_viewport_sample(
 
         let take = slots.min(choices.len());
         let mut out: Vec<u32> = Vec::with_capacity(take);
-        for i in 0..take {
-            let cell_key = choices[i].cell_key as usize;
+        for choice in choices.iter().take(take) {
+            let cell_key = choice.cell_key as usize;
             if let Some(first) = cell_items[cell_key].first() {
                 out.push(first.idx);
             }
         }
-        return Uint32Array::from(out.as_slice());
+        return out;
     }
 
     // One per cell, then allocate remaining slots proportionally to cell density.
@@ -729,7 +870,7 @@ pub fn stable_viewport_sample(
 
     let remaining_slots = slots.saturating_sub(picked.len());
     if remaining_slots == 0 {
-        return Uint32Array::from(picked.as_slice());
+        return picked;
     }
 
     let mut total_remaining: usize = 0;
@@ -737,7 +878,7 @@ pub fn stable_viewport_sample(
         total_remaining += cell_items[cell_key as usize].len().saturating_sub(1);
     }
     if total_remaining == 0 {
-        return Uint32Array::from(picked.as_slice());
+        return picked;
     }
 
     #[derive(Clone, Copy)]
@@ -811,32 +952,1225 @@ pub fn stable_viewport_sample(
     if picked.len() > slots {
         picked.truncate(slots);
     }
+    picked
+}
+
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn stable_viewport_sample(
+    coords: &Float64Array,
+    ids: &Uint32Array,
+    min_lat: f64,
+    max_lat: f64,
+    min_lng: f64,
+    max_lng: f64,
+    slots: usize,
+    seed: u32,
+) -> Uint32Array {
+    let picked = stable_viewport_sample_impl(
+        &coords.to_vec(),
+        &ids.to_vec(),
+        min_lat,
+        max_lat,
+        min_lng,
+        max_lng,
+        slots,
+        seed,
+    );
     Uint32Array::from(picked.as_slice())
 }
 
+/// Normalises polygon-clipping polygons into a valid geo multipolygon, merging overlapping
+/// members so the boolean ops below see well-formed input.
+fn clipping_to_geo_multi_polygon<'a>(
+    raw: impl IntoIterator<Item = &'a ClippingPolygon>,
+) -> MultiPolygon<f64> {
+    let polygons: Vec<Polygon<f64>> = raw
+        .into_iter()
+        .filter_map(clipping_polygon_to_geo_polygon)
+        .collect();
+    if polygons.is_empty() {
+        return MultiPolygon(Vec::new());
+    }
+    unary_union(&polygons)
+}
+
+pub fn union_multi_polygons_impl(inputs: &[ClippingMultiPolygon]) -> ClippingMultiPolygon {
+    geo_multi_polygon_to_clipping(clipping_to_geo_multi_polygon(inputs.iter().flatten()))
+}
+
+/// Folds `op` over `inputs` from the first one, like polygon-clipping's variadic operations.
+fn fold_multi_polygons(
+    inputs: &[ClippingMultiPolygon],
+    op: impl Fn(&MultiPolygon<f64>, &MultiPolygon<f64>) -> MultiPolygon<f64>,
+) -> ClippingMultiPolygon {
+    let mut geoms = inputs.iter().map(clipping_to_geo_multi_polygon);
+    let Some(first) = geoms.next() else {
+        return Vec::new();
+    };
+    geo_multi_polygon_to_clipping(geoms.fold(first, |acc, next| op(&acc, &next)))
+}
+
+/// Area covered by every input.
+pub fn intersect_multi_polygons_impl(inputs: &[ClippingMultiPolygon]) -> ClippingMultiPolygon {
+    fold_multi_polygons(inputs, |a, b| a.intersection(b))
+}
+
+/// The first input minus every later one, e.g. a state minus a county.
+pub fn difference_multi_polygons_impl(inputs: &[ClippingMultiPolygon]) -> ClippingMultiPolygon {
+    fold_multi_polygons(inputs, |a, b| a.difference(b))
+}
+
+/// Area covered by an odd number of inputs.
+pub fn xor_multi_polygons_impl(inputs: &[ClippingMultiPolygon]) -> ClippingMultiPolygon {
+    fold_multi_polygons(inputs, |a, b| a.xor(b))
+}
+
+#[wasm_bindgen]
+pub fn union_multi_polygons(multi_polygons: JsValue) -> Result<JsValue, ScorerError> {
+    let inputs: Vec<ClippingMultiPolygon> = from_js("multipolygon", multi_polygons)?;
+
+    let output = union_multi_polygons_impl(&inputs);
+    to_js("union output", &output)
+}
+
+#[wasm_bindgen]
+pub fn intersect_multi_polygons(multi_polygons: JsValue) -> Result<JsValue, ScorerError> {
+    let inputs: Vec<ClippingMultiPolygon> = from_js("multipolygon", multi_polygons)?;
+
+    let output = intersect_multi_polygons_impl(&inputs);
+    to_js("intersection output", &output)
+}
+
 #[wasm_bindgen]
-pub fn union_multi_polygons(multi_polygons: JsValue) -> Result<JsValue, JsValue> {
-    let inputs: Vec<ClippingMultiPolygon> = serde_wasm_bindgen::from_value(multi_polygons)
-        .map_err(|err| to_js_error("invalid multipolygon payload", err))?;
-
-    let mut polygons: Vec<Polygon<f64>> = Vec::new();
-    for mp in inputs {
-        for raw_poly in mp {
-            if let Some(poly) = clipping_polygon_to_geo_polygon(&raw_poly) {
-                polygons.push(poly);
+pub fn difference_multi_polygons(multi_polygons: JsValue) -> Result<JsValue, ScorerError> {
+    let inputs: Vec<ClippingMultiPolygon> = from_js("multipolygon", multi_polygons)?;
+
+    let output = difference_multi_polygons_impl(&inputs);
+    to_js("difference output", &output)
+}
+
+#[wasm_bindgen]
+pub fn xor_multi_polygons(multi_polygons: JsValue) -> Result<JsValue, ScorerError> {
+    let inputs: Vec<ClippingMultiPolygon> = from_js("multipolygon", multi_polygons)?;
+
+    let output = xor_multi_polygons_impl(&inputs);
+    to_js("xor output", &output)
+}
+
+const KM_PER_DEGREE: f64 = EARTH_RADIUS_KM * std::f64::consts::PI / 180.0;
+const DEFAULT_INDEX_CELL_DEG: f64 = 0.5;
+
+/// Wraps a longitude into -180..180.
+#[inline]
+fn wrap_lng(lng: f64) -> f64 {
+    (lng + 180.0).rem_euclid(360.0) - 180.0
+}
+
+#[inline]
+fn is_lat_lng(lat: f64, lng: f64) -> bool {
+    lat.is_finite() && lng.is_finite() && lat.abs() <= 90.0
+}
+
+/// Lat/lng box. `min_lng > max_lng` means the box crosses the antimeridian.
+#[derive(Debug, Clone, Copy)]
+struct GeoBox {
+    min_lat: f64,
+    max_lat: f64,
+    min_lng: f64,
+    max_lng: f64,
+}
+
+impl GeoBox {
+    /// Smallest box holding every point within `radius_km` of the origin. Covers all
+    /// longitudes when the circle reaches a pole.
+    fn around(lat: f64, lng: f64, radius_km: f64) -> Self {
+        // Pad for rounding, so a point exactly `radius_km` away is still inside.
+        let radius_km = radius_km * (1.0 + 1e-9) + 1e-9;
+        let angular = radius_km / EARTH_RADIUS_KM;
+        let d_lat = radius_km / KM_PER_DEGREE;
+        let min_lat = lat - d_lat;
+        let max_lat = lat + d_lat;
+        let all_lng = |min_lat: f64, max_lat: f64| Self {
+            min_lat: min_lat.max(-90.0),
+            max_lat: max_lat.min(90.0),
+            min_lng: -180.0,
+            max_lng: 180.0,
+        };
+        if min_lat <= -90.0 || max_lat >= 90.0 || angular >= std::f64::consts::FRAC_PI_2 {
+            return all_lng(min_lat, max_lat);
+        }
+        let sin_d_lng = angular.sin() / to_rad(lat).cos();
+        if sin_d_lng >= 1.0 {
+            return all_lng(min_lat, max_lat);
+        }
+        let d_lng = sin_d_lng.asin().to_degrees();
+        Self {
+            min_lat,
+            max_lat,
+            min_lng: wrap_lng(lng - d_lng),
+            max_lng: wrap_lng(lng + d_lng),
+        }
+    }
+
+    fn covers_all_lng(&self) -> bool {
+        self.max_lng - self.min_lng >= 360.0 || (self.min_lng <= -180.0 && self.max_lng >= 180.0)
+    }
+
+    fn contains(&self, lat: f64, lng: f64) -> bool {
+        if lat < self.min_lat || lat > self.max_lat {
+            return false;
+        }
+        if self.covers_all_lng() {
+            return true;
+        }
+        let lng = wrap_lng(lng);
+        let min_lng = wrap_lng(self.min_lng);
+        let max_lng = wrap_lng(self.max_lng);
+        if min_lng <= max_lng {
+            lng >= min_lng && lng <= max_lng
+        } else {
+            lng >= min_lng || lng <= max_lng
+        }
+    }
+
+    /// The box as one or two longitude ranges that do not cross the antimeridian.
+    fn lng_ranges(&self) -> Vec<(f64, f64)> {
+        if self.covers_all_lng() {
+            return vec![(-180.0, 180.0)];
+        }
+        let min_lng = wrap_lng(self.min_lng);
+        let max_lng = wrap_lng(self.max_lng);
+        if min_lng <= max_lng {
+            vec![(min_lng, max_lng)]
+        } else {
+            vec![(min_lng, 180.0), (-180.0, max_lng)]
+        }
+    }
+}
+
+/// Sorts `(index, distance_km)` pairs nearest first, ties by index.
+fn sort_by_distance(hits: &mut [(u32, f64)]) {
+    hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+}
+
+/// Contact coordinates kept on the WASM side between calls, bucketed into a lat/lng grid so
+/// that each query only visits the cells it overlaps. Points are keyed by caller-chosen ids;
+/// re-inserting an id moves it.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    cell_deg: f64,
+    points: HashMap<u32, (f64, f64)>,
+    cells: HashMap<(i64, i64), Vec<u32>>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_INDEX_CELL_DEG)
+    }
+}
+
+#[wasm_bindgen]
+impl SpatialIndex {
+    /// `cell_deg` is the grid cell size in degrees; non-positive values fall back to 0.5.
+    #[wasm_bindgen(constructor)]
+    pub fn new(cell_deg: f64) -> Self {
+        let cell_deg = if cell_deg.is_finite() && cell_deg > 0.0 {
+            cell_deg
+        } else {
+            DEFAULT_INDEX_CELL_DEG
+        };
+        Self {
+            cell_deg,
+            points: HashMap::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.cells.clear();
+    }
+
+    /// Adds or moves `id`. Returns `false`, leaving the index unchanged, for coordinates that
+    /// are not a valid lat/lng.
+    pub fn insert(&mut self, id: u32, lat: f64, lng: f64) -> bool {
+        if !is_lat_lng(lat, lng) {
+            return false;
+        }
+        self.remove(id);
+        let lng = wrap_lng(lng);
+        self.points.insert(id, (lat, lng));
+        self.cells
+            .entry(self.cell_of(lat, lng))
+            .or_default()
+            .push(id);
+        true
+    }
+
+    /// Returns whether `id` was in the index.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let Some((lat, lng)) = self.points.remove(&id) else {
+            return false;
+        };
+        let key = self.cell_of(lat, lng);
+        if let Some(ids) = self.cells.get_mut(&key) {
+            if let Some(position) = ids.iter().position(|&other| other == id) {
+                ids.swap_remove(position);
+            }
+            if ids.is_empty() {
+                self.cells.remove(&key);
             }
         }
+        true
     }
 
-    if polygons.is_empty() {
-        let empty: ClippingMultiPolygon = Vec::new();
-        return serde_wasm_bindgen::to_value(&empty)
-            .map_err(|err| to_js_error("failed to serialize union output", err));
+    /// Inserts `ids[i]` at `coords[2i], coords[2i + 1]` (lat, lng) and returns how many were
+    /// accepted. Throws, inserting nothing, unless `coords` holds exactly two values per id.
+    pub fn insert_many(&mut self, ids: &[u32], coords: &[f64]) -> Result<usize, ScorerError> {
+        self.insert_many_impl(ids, coords)
+    }
+
+    pub fn remove_many(&mut self, ids: &[u32]) -> usize {
+        self.remove_many_impl(ids)
+    }
+
+    /// Ids inside the box, ascending. `min_lng > max_lng` selects a box across the
+    /// antimeridian.
+    pub fn bbox(&self, min_lat: f64, max_lat: f64, min_lng: f64, max_lng: f64) -> Uint32Array {
+        let ids = self.bbox_impl(min_lat, max_lat, min_lng, max_lng);
+        Uint32Array::from(ids.as_slice())
+    }
+
+    /// Ids within `radius_km` of the origin, nearest first.
+    pub fn within_radius(&self, lat: f64, lng: f64, radius_km: f64) -> Uint32Array {
+        let hits = self.within_radius_impl(lat, lng, radius_km);
+        Uint32Array::from(
+            hits.iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>()
+                .as_slice(),
+        )
+    }
+
+    /// The `k` ids nearest to the origin, nearest first.
+    pub fn nearest(&self, lat: f64, lng: f64, k: usize) -> Uint32Array {
+        let hits = self.nearest_impl(lat, lng, k);
+        Uint32Array::from(
+            hits.iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>()
+                .as_slice(),
+        )
+    }
+
+    /// Ids inside `ring` (flat lng/lat pairs, as in `point_in_ring`), ascending.
+    pub fn in_polygon(&self, ring: &Float64Array) -> Uint32Array {
+        let ids = self.in_polygon_impl(&ring.to_vec());
+        Uint32Array::from(ids.as_slice())
+    }
+}
+
+impl SpatialIndex {
+    fn cell_of(&self, lat: f64, lng: f64) -> (i64, i64) {
+        (
+            (lng / self.cell_deg).floor() as i64,
+            (lat / self.cell_deg).floor() as i64,
+        )
+    }
+
+    pub fn insert_many_impl(&mut self, ids: &[u32], coords: &[f64]) -> Result<usize, ScorerError> {
+        let (expected, actual) = (ids.len() * 2, coords.len());
+        if actual != expected {
+            return Err(ScorerError::invalid_payload(
+                "coords",
+                format!("expected {expected} values, got {actual}"),
+            ));
+        }
+        Ok(ids
+            .iter()
+            .zip(coords.chunks_exact(2))
+            .filter(|(&id, pair)| self.insert(id, pair[0], pair[1]))
+            .count())
+    }
+
+    pub fn remove_many_impl(&mut self, ids: &[u32]) -> usize {
+        ids.iter().filter(|&&id| self.remove(id)).count()
+    }
+
+    /// Every indexed `(id, lat, lng)` inside `area`, unordered.
+    fn points_in(&self, area: &GeoBox) -> Vec<(u32, f64, f64)> {
+        let mut out: Vec<(u32, f64, f64)> = Vec::new();
+        if area.min_lat > area.max_lat || self.points.is_empty() {
+            return out;
+        }
+        let min_y = (area.min_lat.max(-90.0) / self.cell_deg).floor() as i64;
+        let max_y = (area.max_lat.min(90.0) / self.cell_deg).floor() as i64;
+        let mut visited: HashSet<(i64, i64)> = HashSet::new();
+        for (min_lng, max_lng) in area.lng_ranges() {
+            let min_x = (min_lng / self.cell_deg).floor() as i64;
+            let max_x = (max_lng / self.cell_deg).floor() as i64;
+            let span = (max_x - min_x + 1).saturating_mul(max_y - min_y + 1);
+            let in_range =
+                |&(x, y): &(i64, i64)| x >= min_x && x <= max_x && y >= min_y && y <= max_y;
+            // Walk the occupied cells instead when the box spans more cells than are in use.
+            let keys: Vec<(i64, i64)> = if span > self.cells.len() as i64 {
+                self.cells.keys().copied().filter(in_range).collect()
+            } else {
+                (min_y..=max_y)
+                    .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+                    .filter(|key| self.cells.contains_key(key))
+                    .collect()
+            };
+            for key in keys {
+                // The two ranges of an antimeridian box can share an edge cell.
+                if !visited.insert(key) {
+                    continue;
+                }
+                for &id in &self.cells[&key] {
+                    let (lat, lng) = self.points[&id];
+                    if area.contains(lat, lng) {
+                        out.push((id, lat, lng));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn bbox_impl(&self, min_lat: f64, max_lat: f64, min_lng: f64, max_lng: f64) -> Vec<u32> {
+        if [min_lat, max_lat, min_lng, max_lng]
+            .iter()
+            .any(|v| !v.is_finite())
+        {
+            return Vec::new();
+        }
+        let area = GeoBox {
+            min_lat,
+            max_lat,
+            min_lng,
+            max_lng,
+        };
+        let mut ids: Vec<u32> = self
+            .points_in(&area)
+            .into_iter()
+            .map(|(id, ..)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// `(id, distance_km)` for every point within `radius_km`, nearest first.
+    pub fn within_radius_impl(&self, lat: f64, lng: f64, radius_km: f64) -> Vec<(u32, f64)> {
+        if !lat.is_finite() || !lng.is_finite() || radius_km.is_nan() || radius_km < 0.0 {
+            return Vec::new();
+        }
+        let mut hits: Vec<(u32, f64)> = self
+            .points_in(&GeoBox::around(lat, lng, radius_km))
+            .into_iter()
+            .map(|(id, p_lat, p_lng)| (id, haversine_km_impl(lat, lng, p_lat, p_lng)))
+            .filter(|(_, distance)| *distance <= radius_km)
+            .collect();
+        sort_by_distance(&mut hits);
+        hits
+    }
+
+    /// `(id, distance_km)` for the `k` points nearest to the origin, nearest first.
+    pub fn nearest_impl(&self, lat: f64, lng: f64, k: usize) -> Vec<(u32, f64)> {
+        if k == 0 || !lat.is_finite() || !lng.is_finite() {
+            return Vec::new();
+        }
+        // Any k points within r include the k nearest, so grow r until that many turn up.
+        let max_distance = std::f64::consts::PI * EARTH_RADIUS_KM;
+        let mut radius_km = self.cell_deg * KM_PER_DEGREE;
+        loop {
+            let mut hits = self.within_radius_impl(lat, lng, radius_km);
+            if hits.len() >= k || radius_km >= max_distance {
+                hits.truncate(k);
+                return hits;
+            }
+            radius_km = (radius_km * 2.0).min(max_distance);
+        }
+    }
+
+    pub fn in_polygon_impl(&self, ring: &[f64]) -> Vec<u32> {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for pair in ring.chunks_exact(2) {
+            min_x = min_x.min(pair[0]);
+            max_x = max_x.max(pair[0]);
+            min_y = min_y.min(pair[1]);
+            max_y = max_y.max(pair[1]);
+        }
+        if ring.len() < 6 || !(max_x - min_x).is_finite() || !(max_y - min_y).is_finite() {
+            return Vec::new();
+        }
+        let area = GeoBox {
+            min_lat: min_y,
+            max_lat: max_y,
+            min_lng: min_x,
+            max_lng: max_x,
+        };
+        // A ring wider than the world is not a box across the antimeridian.
+        let area = if max_x - min_x >= 360.0 {
+            GeoBox {
+                min_lng: -180.0,
+                max_lng: 180.0,
+                ..area
+            }
+        } else {
+            area
+        };
+        let mut ids: Vec<u32> = self
+            .points_in(&area)
+            .into_iter()
+            .filter(|&(_, lat, lng)| point_in_ring_impl(lng, lat, ring))
+            .map(|(id, ..)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+}
+
+/// Indices into a flat lat/lng array with their distance from the origin, nearest first.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct NearbyPoints {
+    indices: Vec<u32>,
+    distances_km: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl NearbyPoints {
+    #[wasm_bindgen(getter)]
+    pub fn indices(&self) -> Uint32Array {
+        Uint32Array::from(self.indices.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn distances_km(&self) -> Float64Array {
+        Float64Array::from(self.distances_km.as_slice())
+    }
+}
+
+impl From<Vec<(u32, f64)>> for NearbyPoints {
+    fn from(hits: Vec<(u32, f64)>) -> Self {
+        let (indices, distances_km) = hits.into_iter().unzip();
+        Self {
+            indices,
+            distances_km,
+        }
+    }
+}
+
+/// `(index, distance_km)` for every pair of `coords` (flat lat, lng) within `radius_km` of the
+/// origin, nearest first. Points outside the radius' bounding box are skipped before any
+/// trigonometry.
+pub fn within_radius_km_impl(
+    origin_lat: f64,
+    origin_lng: f64,
+    coords: &[f64],
+    radius_km: f64,
+) -> Vec<(u32, f64)> {
+    if !is_lat_lng(origin_lat, origin_lng) || radius_km.is_nan() || radius_km < 0.0 {
+        return Vec::new();
+    }
+    let area = GeoBox::around(origin_lat, origin_lng, radius_km);
+    let mut hits: Vec<(u32, f64)> = Vec::new();
+    for (idx, pair) in coords.chunks_exact(2).enumerate() {
+        let (lat, lng) = (pair[0], pair[1]);
+        if !is_lat_lng(lat, lng) || !area.contains(lat, lng) {
+            continue;
+        }
+        let distance = haversine_km_impl(origin_lat, origin_lng, lat, lng);
+        if distance <= radius_km {
+            hits.push((idx as u32, distance));
+        }
+    }
+    sort_by_distance(&mut hits);
+    hits
+}
+
+/// `(index, distance_km)` for the `k` pairs of `coords` nearest to the origin, nearest first.
+pub fn knn_haversine_impl(
+    origin_lat: f64,
+    origin_lng: f64,
+    coords: &[f64],
+    k: usize,
+) -> Vec<(u32, f64)> {
+    if k == 0 || !is_lat_lng(origin_lat, origin_lng) {
+        return Vec::new();
+    }
+
+    // Rank by a flat-earth approximation first. The true distance to the k-th closest
+    // candidate bounds the answer, so one radius query over that bound is exact.
+    let lng_scale = to_rad(origin_lat).cos();
+    let mut candidates: Vec<(u32, f64)> = coords
+        .chunks_exact(2)
+        .enumerate()
+        .filter(|(_, pair)| is_lat_lng(pair[0], pair[1]))
+        .map(|(idx, pair)| {
+            let d_lat = pair[0] - origin_lat;
+            let d_lng = wrap_lng(pair[1] - origin_lng) * lng_scale;
+            (idx as u32, d_lat * d_lat + d_lng * d_lng)
+        })
+        .collect();
+
+    if candidates.len() > k {
+        candidates.select_nth_unstable_by(k - 1, |a, b| a.1.total_cmp(&b.1));
+        let radius_km = candidates[..k]
+            .iter()
+            .map(|&(idx, _)| {
+                let base = idx as usize * 2;
+                haversine_km_impl(origin_lat, origin_lng, coords[base], coords[base + 1])
+            })
+            .fold(0.0, f64::max);
+        let mut hits = within_radius_km_impl(origin_lat, origin_lng, coords, radius_km);
+        hits.truncate(k);
+        return hits;
+    }
+
+    let mut hits: Vec<(u32, f64)> = candidates
+        .into_iter()
+        .map(|(idx, _)| {
+            let base = idx as usize * 2;
+            (
+                idx,
+                haversine_km_impl(origin_lat, origin_lng, coords[base], coords[base + 1]),
+            )
+        })
+        .collect();
+    sort_by_distance(&mut hits);
+    hits
+}
+
+#[wasm_bindgen]
+pub fn within_radius_km(
+    origin_lat: f64,
+    origin_lng: f64,
+    coords: &Float64Array,
+    radius_km: f64,
+) -> NearbyPoints {
+    within_radius_km_impl(origin_lat, origin_lng, &coords.to_vec(), radius_km).into()
+}
+
+#[wasm_bindgen]
+pub fn knn_haversine(
+    origin_lat: f64,
+    origin_lng: f64,
+    coords: &Float64Array,
+    k: usize,
+) -> NearbyPoints {
+    knn_haversine_impl(origin_lat, origin_lng, &coords.to_vec(), k).into()
+}
+
+/// Inverse of [`lat_lng_to_world_pixel_impl`].
+#[inline]
+fn world_pixel_to_lat_lng(x: f64, y: f64, world_size: f64) -> (f64, f64) {
+    let lng = (x / world_size) * 360.0 - 180.0;
+    let lat = (std::f64::consts::PI * (1.0 - 2.0 * y / world_size))
+        .sinh()
+        .atan()
+        .to_degrees();
+    (lat, lng)
+}
+
+/// Same option names and defaults as the `supercluster` JS package.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct ClusterOptions {
+    /// Cluster radius in pixels of a tile `extent` pixels wide.
+    pub radius: f64,
+    pub extent: f64,
+    pub min_zoom: u8,
+    /// Deepest zoom that clusters; above it every point is shown on its own.
+    pub max_zoom: u8,
+    /// Fewest points that form a cluster.
+    pub min_points: u32,
+}
+
+impl Default for ClusterOptions {
+    fn default() -> Self {
+        Self {
+            radius: 40.0,
+            extent: 512.0,
+            min_zoom: 0,
+            max_zoom: 16,
+            min_points: 2,
+        }
+    }
+}
+
+/// A cluster or a single point visible at some zoom.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarkerCluster {
+    /// Weighted centroid for clusters, the original coordinates for single points.
+    pub lat: f64,
+    pub lng: f64,
+    pub point_count: u32,
+    /// Zoom at which the cluster splits into its children. Not set for single points.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expansion_zoom: Option<u8>,
+    /// Index into the input coordinates, for single points.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClusterNode {
+    /// Position in the unit world square, see [`lat_lng_to_world_pixel_impl`].
+    x: f64,
+    y: f64,
+    count: u32,
+    /// `Some` for single points.
+    point: Option<u32>,
+    /// Zoom the cluster was formed at.
+    zoom: u8,
+}
+
+/// Hierarchical greedy clustering in the style of `supercluster`. Every zoom level from
+/// `max_zoom` down to `min_zoom` is built once, by merging the nodes of the level above that
+/// lie within `radius` pixels of each other, so queries only filter one level by bbox.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct ClusterIndex {
+    options: ClusterOptions,
+    coords: Vec<f64>,
+    /// Nodes per zoom, from `min_zoom` to `max_zoom + 1` (single points).
+    levels: Vec<Vec<ClusterNode>>,
+}
+
+#[wasm_bindgen]
+impl ClusterIndex {
+    /// Clusters `coords` (flat lat, lng). Pairs that are not valid coordinates are left out.
+    #[wasm_bindgen(constructor)]
+    pub fn new(coords: &Float64Array, options: JsValue) -> Result<ClusterIndex, ScorerError> {
+        let options: Option<ClusterOptions> = from_js("options", options)?;
+        Ok(Self::build(&coords.to_vec(), options.unwrap_or_default()))
+    }
+
+    /// Clusters and points inside the box at `zoom` (floored), as
+    /// `{ lat, lng, point_count, expansion_zoom?, index? }` objects. `min_lng > max_lng`
+    /// selects a box across the antimeridian.
+    pub fn clusters(
+        &self,
+        min_lat: f64,
+        max_lat: f64,
+        min_lng: f64,
+        max_lng: f64,
+        zoom: f64,
+    ) -> Result<JsValue, ScorerError> {
+        let clusters = self.clusters_impl(min_lat, max_lat, min_lng, max_lng, zoom);
+        to_js("clusters", &clusters)
+    }
+}
+
+impl ClusterIndex {
+    pub fn build(coords: &[f64], options: ClusterOptions) -> Self {
+        let max_zoom = options.max_zoom.min(30);
+        let min_zoom = options.min_zoom.min(max_zoom);
+        let options = ClusterOptions {
+            min_zoom,
+            max_zoom,
+            ..options
+        };
+
+        let points: Vec<ClusterNode> = coords
+            .chunks_exact(2)
+            .enumerate()
+            .filter(|(_, pair)| is_lat_lng(pair[0], pair[1]))
+            .map(|(idx, pair)| {
+                let (x, y) = lat_lng_to_world_pixel_impl(pair[0], pair[1], 1.0);
+                ClusterNode {
+                    x,
+                    y,
+                    count: 1,
+                    point: Some(idx as u32),
+                    zoom: max_zoom + 1,
+                }
+            })
+            .collect();
+
+        let mut levels: Vec<Vec<ClusterNode>> = vec![points];
+        for zoom in (min_zoom..=max_zoom).rev() {
+            let radius = options.radius / (options.extent * 2_f64.powi(zoom as i32));
+            let next = cluster_level(&levels[levels.len() - 1], zoom, radius, options.min_points);
+            levels.push(next);
+        }
+        levels.reverse();
+
+        Self {
+            options,
+            coords: coords.to_vec(),
+            levels,
+        }
     }
 
-    let unioned = unary_union(&polygons);
-    let output = geo_multi_polygon_to_clipping(unioned);
-    serde_wasm_bindgen::to_value(&output)
-        .map_err(|err| to_js_error("failed to serialize union output", err))
+    pub fn clusters_impl(
+        &self,
+        min_lat: f64,
+        max_lat: f64,
+        min_lng: f64,
+        max_lng: f64,
+        zoom: f64,
+    ) -> Vec<MarkerCluster> {
+        if [min_lat, max_lat, min_lng, max_lng, zoom]
+            .iter()
+            .any(|v| !v.is_finite())
+        {
+            return Vec::new();
+        }
+        let max_level = self.options.max_zoom as f64 + 1.0;
+        let zoom = zoom.floor().clamp(self.options.min_zoom as f64, max_level) as u8;
+        let level = &self.levels[(zoom - self.options.min_zoom) as usize];
+        let area = GeoBox {
+            min_lat,
+            max_lat,
+            min_lng,
+            max_lng,
+        };
+
+        level
+            .iter()
+            .filter_map(|node| {
+                let (lat, lng) = match node.point {
+                    Some(idx) => {
+                        let base = idx as usize * 2;
+                        (self.coords[base], self.coords[base + 1])
+                    }
+                    None => world_pixel_to_lat_lng(node.x, node.y, 1.0),
+                };
+                area.contains(lat, lng).then(|| MarkerCluster {
+                    lat,
+                    lng,
+                    point_count: node.count,
+                    expansion_zoom: node.point.is_none().then_some(node.zoom + 1),
+                    index: node.point,
+                })
+            })
+            .collect()
+    }
+}
+
+/// One level of [`ClusterIndex`]: greedily merges every node of `nodes` with its unclaimed
+/// neighbours within `radius` (unit world coordinates).
+fn cluster_level(
+    nodes: &[ClusterNode],
+    zoom: u8,
+    radius: f64,
+    min_points: u32,
+) -> Vec<ClusterNode> {
+    let cell = |x: f64, y: f64| ((x / radius).floor() as i64, (y / radius).floor() as i64);
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        grid.entry(cell(node.x, node.y)).or_default().push(idx);
+    }
+
+    let radius_sq = radius * radius;
+    let mut claimed = vec![false; nodes.len()];
+    let mut out: Vec<ClusterNode> = Vec::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        if claimed[idx] {
+            continue;
+        }
+        claimed[idx] = true;
+
+        let (cx, cy) = cell(node.x, node.y);
+        let mut neighbors: Vec<usize> = Vec::new();
+        for dx in -1_i64..=1 {
+            for dy in -1_i64..=1 {
+                let Some(candidates) = grid.get(&(cx + dx, cy + dy)) else {
+                    continue;
+                };
+                for &other in candidates {
+                    let (ddx, ddy) = (nodes[other].x - node.x, nodes[other].y - node.y);
+                    if !claimed[other] && ddx * ddx + ddy * ddy <= radius_sq {
+                        neighbors.push(other);
+                    }
+                }
+            }
+        }
+
+        let count: u32 = node.count + neighbors.iter().map(|&n| nodes[n].count).sum::<u32>();
+        if neighbors.is_empty() || count < min_points {
+            // Too few to cluster; neighbours stay free for a later node.
+            out.push(*node);
+            continue;
+        }
+
+        let mut wx = node.x * node.count as f64;
+        let mut wy = node.y * node.count as f64;
+        for &other in &neighbors {
+            claimed[other] = true;
+            wx += nodes[other].x * nodes[other].count as f64;
+            wy += nodes[other].y * nodes[other].count as f64;
+        }
+        out.push(ClusterNode {
+            x: wx / count as f64,
+            y: wy / count as f64,
+            count,
+            point: None,
+            zoom,
+        });
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimplifyMethod {
+    /// Drops vertices closer than the tolerance to the simplified line.
+    #[default]
+    DouglasPeucker,
+    /// Drops vertices whose triangle with their neighbours is smaller than the tolerance
+    /// squared; keeps shapes rounder than Douglas-Peucker at the same tolerance.
+    VisvalingamWhyatt,
+}
+
+fn simplify_ring(
+    raw: &ClippingRing,
+    method: SimplifyMethod,
+    tolerance_px: f64,
+    world_size: f64,
+) -> Option<ClippingRing> {
+    let ring = normalize_ring(raw)?;
+    let projected: LineString<f64> = ring
+        .iter()
+        .map(|&[lng, lat]| {
+            let (x, y) = lat_lng_to_world_pixel_impl(lat, lng, world_size);
+            Coord { x, y }
+        })
+        .collect();
+    let kept = match method {
+        SimplifyMethod::DouglasPeucker => projected.simplify_idx(tolerance_px),
+        SimplifyMethod::VisvalingamWhyatt => projected.simplify_vw_idx(tolerance_px * tolerance_px),
+    };
+    normalize_ring(&kept.into_iter().map(|idx| ring[idx]).collect())
 }
 
+/// Simplifies every ring as it would be drawn at `zoom`, so `tolerance_px` is in screen pixels
+/// of a 256 px tile map. Vertices keep their original coordinates. Polygons whose outer ring
+/// collapses are dropped, as are holes that collapse or are no longer inside the outer ring.
+pub fn simplify_multi_polygon_impl(
+    input: &ClippingMultiPolygon,
+    method: SimplifyMethod,
+    tolerance_px: f64,
+    zoom: f64,
+) -> ClippingMultiPolygon {
+    let world_size = TILE_SIZE * 2_f64.powf(zoom);
+    let tolerance_px = if tolerance_px.is_finite() {
+        tolerance_px.max(0.0)
+    } else {
+        0.0
+    };
+    if !world_size.is_finite() || world_size <= 0.0 {
+        return input.clone();
+    }
+
+    input
+        .iter()
+        .filter_map(|polygon| {
+            let (exterior, holes) = polygon.split_first()?;
+            let exterior = simplify_ring(exterior, method, tolerance_px, world_size)?;
+            let outline = Polygon::new(ring_to_linestring(&exterior), Vec::new());
+            let mut rings: ClippingPolygon = vec![exterior];
+            rings.extend(
+                holes
+                    .iter()
+                    .filter_map(|hole| simplify_ring(hole, method, tolerance_px, world_size))
+                    // Rings are simplified independently, so a hole can end up crossing the
+                    // simplified outer ring.
+                    .filter(|hole| outline.contains(&ring_to_linestring(hole))),
+            );
+            Some(rings)
+        })
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn simplify_douglas_peucker(
+    multi_polygon: JsValue,
+    tolerance_px: f64,
+    zoom: f64,
+) -> Result<JsValue, ScorerError> {
+    let input: ClippingMultiPolygon = from_js("multipolygon", multi_polygon)?;
+
+    let method = SimplifyMethod::DouglasPeucker;
+    let output = simplify_multi_polygon_impl(&input, method, tolerance_px, zoom);
+    to_js("simplified output", &output)
+}
+
+#[wasm_bindgen]
+pub fn simplify_visvalingam_whyatt(
+    multi_polygon: JsValue,
+    tolerance_px: f64,
+    zoom: f64,
+) -> Result<JsValue, ScorerError> {
+    let input: ClippingMultiPolygon = from_js("multipolygon", multi_polygon)?;
+
+    let method = SimplifyMethod::VisvalingamWhyatt;
+    let output = simplify_multi_polygon_impl(&input, method, tolerance_px, zoom);
+    to_js("simplified output", &output)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct PyramidOptions {
+    pub method: SimplifyMethod,
+    pub tolerance_px: f64,
+    pub min_zoom: u8,
+    /// Zooms above this get the `max_zoom` level.
+    pub max_zoom: u8,
+}
+
+impl Default for PyramidOptions {
+    fn default() -> Self {
+        Self {
+            method: SimplifyMethod::DouglasPeucker,
+            tolerance_px: 0.5,
+            min_zoom: 0,
+            max_zoom: 14,
+        }
+    }
+}
+
+/// One polygon simplified once per integer zoom, so redrawing an overlay while zooming only
+/// picks a precomputed level.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PolygonPyramid {
+    min_zoom: u8,
+    levels: Vec<ClippingMultiPolygon>,
+}
+
+#[wasm_bindgen]
+impl PolygonPyramid {
+    #[wasm_bindgen(constructor)]
+    pub fn new(multi_polygon: JsValue, options: JsValue) -> Result<PolygonPyramid, ScorerError> {
+        let input: ClippingMultiPolygon = from_js("multipolygon", multi_polygon)?;
+        let options: Option<PyramidOptions> = from_js("options", options)?;
+        Ok(Self::build(&input, options.unwrap_or_default()))
+    }
+
+    /// The level for `zoom` (floored and clamped to the pyramid), in polygon-clipping format.
+    pub fn at_zoom(&self, zoom: f64) -> Result<JsValue, ScorerError> {
+        to_js("pyramid level", self.level(zoom))
+    }
+}
+
+impl PolygonPyramid {
+    pub fn build(input: &ClippingMultiPolygon, options: PyramidOptions) -> Self {
+        let max_zoom = options.max_zoom.min(30);
+        let min_zoom = options.min_zoom.min(max_zoom);
+        let levels = (min_zoom..=max_zoom)
+            .map(|zoom| {
+                simplify_multi_polygon_impl(
+                    input,
+                    options.method,
+                    options.tolerance_px,
+                    zoom as f64,
+                )
+            })
+            .collect();
+        Self { min_zoom, levels }
+    }
+
+    pub fn level(&self, zoom: f64) -> &ClippingMultiPolygon {
+        let max_level = self.levels.len() - 1;
+        let offset = if zoom.is_nan() {
+            max_level
+        } else {
+            (zoom.floor() - self.min_zoom as f64).clamp(0.0, max_level as f64) as usize
+        };
+        &self.levels[offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f64, max: f64) -> ClippingRing {
+        vec![[min, min], [max, min], [max, max], [min, max], [min, min]]
+    }
+
+    #[test]
+    fn simplify_drops_holes_left_outside_the_outer_ring() {
+        // The hole pokes above lat 10 into a bump that the outer ring loses at zoom 0.
+        let exterior = vec![
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [5.0, 10.3],
+            [0.0, 10.0],
+        ];
+        let hole = vec![[4.0, 9.8], [6.0, 9.8], [6.0, 10.2], [4.0, 10.2]];
+        let input = vec![vec![exterior, hole]];
+        let method = SimplifyMethod::DouglasPeucker;
+        let simplified = simplify_multi_polygon_impl(&input, method, 0.25, 0.0);
+        assert_eq!(simplified.len(), 1);
+        assert_eq!(simplified[0].len(), 1);
+        assert_eq!(simplified[0][0].len(), 5);
+    }
+
+    #[test]
+    fn difference_of_an_inner_square_leaves_a_hole() {
+        let inputs = vec![vec![vec![square(0.0, 10.0)]], vec![vec![square(3.0, 7.0)]]];
+        let difference = difference_multi_polygons_impl(&inputs);
+        assert_eq!(difference.len(), 1);
+        assert_eq!(difference[0].len(), 2);
+        let area = ring_abs_area(&difference[0][0]) - ring_abs_area(&difference[0][1]);
+        assert!((area - 84.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cluster_counts_cover_every_point_at_every_zoom() {
+        // 17 and 23 are coprime, so all 300 positions are distinct.
+        let coords: Vec<f64> = (0..300_u32)
+            .flat_map(|i| {
+                let (row, column) = (f64::from(i % 17), f64::from(i % 23));
+                [40.0 + row * 0.37, column * 0.51 - 100.0]
+            })
+            .collect();
+        let index = ClusterIndex::build(&coords, ClusterOptions::default());
+        for zoom in 0..=18 {
+            let clusters = index.clusters_impl(-90.0, 90.0, -180.0, 180.0, zoom as f64);
+            let total: u32 = clusters.iter().map(|cluster| cluster.point_count).sum();
+            assert_eq!(total, 300, "zoom {zoom}");
+        }
+    }
+
+    #[test]
+    fn union_merges_overlapping_inputs() {
+        let inputs = vec![vec![vec![square(0.0, 2.0)]], vec![vec![square(1.0, 3.0)]]];
+        let union = union_multi_polygons_impl(&inputs);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].len(), 1);
+    }
+
+    #[test]
+    fn insert_many_rejects_mismatched_coords() {
+        let mut index = SpatialIndex::new(0.5);
+        assert!(index.insert_many_impl(&[1, 2], &[0.0, 0.0, 1.0]).is_err());
+        assert!(index.bbox_impl(-90.0, 90.0, -180.0, 180.0).is_empty());
+        let inserted = index.insert_many_impl(&[1, 2], &[0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(inserted, Ok(2));
+    }
+
+    #[test]
+    fn antimeridian_box_sharing_a_cell_returns_each_id_once() {
+        let mut index = SpatialIndex::new(0.5);
+        index.insert_many_impl(&[7], &[0.0, 10.4]).unwrap();
+        assert_eq!(index.bbox_impl(-1.0, 1.0, 10.3, 10.1), vec![7]);
+    }
+
+    #[test]
+    fn nearest_us_states_rank_by_centroid_distance() {
+        let nearest = nearest_us_states_impl("Rhode Island", 3);
+        assert_eq!(
+            nearest,
+            vec!["Massachusetts", "Connecticut", "New Hampshire"]
+        );
+
+        let ranked = nearest_us_states_impl("Kansas", US_STATE_CENTROIDS.len());
+        assert_eq!(ranked.len(), US_STATE_CENTROIDS.len() - 1);
+        assert!(!ranked.contains(&"Kansas"));
+        let (_, _, lat, lng) = US_STATE_CENTROIDS[us_state_index("Kansas").unwrap()];
+        let distance = |name: &str| {
+            let idx = US_STATE_CENTROIDS
+                .iter()
+                .position(|row| row.0 == name)
+                .unwrap();
+            let (_, _, state_lat, state_lng) = US_STATE_CENTROIDS[idx];
+            haversine_km_impl(lat, lng, state_lat, state_lng)
+        };
+        assert!(ranked
+            .windows(2)
+            .all(|pair| distance(pair[0]) <= distance(pair[1])));
+    }
+
+    #[test]
+    fn nearest_us_states_accept_codes_and_aliases() {
+        let by_name = nearest_us_states_impl("California", 4);
+        assert_eq!(nearest_us_states_impl("CA", 4), by_name);
+        assert_eq!(nearest_us_states_impl("Calif.", 4), by_name);
+        assert_eq!(
+            nearest_us_states_impl("N.Y.", 2),
+            nearest_us_states_impl("new york", 2)
+        );
+        assert!(nearest_us_states_impl("Ontario", 3).is_empty());
+        assert!(nearest_us_states_impl("Atlantis", 3).is_empty());
+    }
+
+    /// Points spread over the whole globe by a fixed LCG, plus one invalid pair.
+    fn scattered_coords(count: usize) -> Vec<f64> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (state >> 11) as f64 / (1_u64 << 53) as f64
+        };
+        let mut coords: Vec<f64> = Vec::with_capacity(count * 2 + 2);
+        for _ in 0..count {
+            coords.push(next() * 180.0 - 90.0);
+            coords.push(next() * 360.0 - 180.0);
+        }
+        coords.extend([f64::NAN, 10.0]);
+        coords
+    }
+
+    fn brute_force_distances(lat: f64, lng: f64, coords: &[f64]) -> Vec<(u32, f64)> {
+        let mut hits: Vec<(u32, f64)> = coords
+            .chunks_exact(2)
+            .enumerate()
+            .filter(|(_, pair)| is_lat_lng(pair[0], pair[1]))
+            .map(|(idx, pair)| (idx as u32, haversine_km_impl(lat, lng, pair[0], pair[1])))
+            .collect();
+        sort_by_distance(&mut hits);
+        hits
+    }
+
+    const ORIGINS: [(f64, f64); 4] = [(40.7, -74.0), (89.5, 0.0), (-0.5, 179.9), (-60.0, -10.0)];
+
+    #[test]
+    fn knn_matches_brute_force() {
+        let coords = scattered_coords(2_000);
+        for (lat, lng) in ORIGINS {
+            let expected = brute_force_distances(lat, lng, &coords);
+            for k in [1, 7, 250, 5_000] {
+                let nearest = knn_haversine_impl(lat, lng, &coords, k);
+                let count = k.min(expected.len());
+                assert_eq!(nearest, expected[..count], "origin ({lat}, {lng}), k {k}");
+            }
+        }
+        assert!(knn_haversine_impl(40.7, -74.0, &coords, 0).is_empty());
+    }
+
+    #[test]
+    fn within_radius_matches_brute_force() {
+        let coords = scattered_coords(2_000);
+        for (lat, lng) in ORIGINS {
+            let expected = brute_force_distances(lat, lng, &coords);
+            for radius_km in [0.0, 300.0, 2_500.0, 25_000.0] {
+                let within = within_radius_km_impl(lat, lng, &coords, radius_km);
+                let inside: Vec<(u32, f64)> = expected
+                    .iter()
+                    .copied()
+                    .filter(|&(_, distance)| distance <= radius_km)
+                    .collect();
+                assert_eq!(within, inside, "origin ({lat}, {lng}), radius {radius_km}");
+            }
+        }
+        assert!(within_radius_km_impl(40.7, -74.0, &coords, -1.0).is_empty());
+    }
+}
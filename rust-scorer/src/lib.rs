@@ -2,57 +2,370 @@ use js_sys::{Array, JsString, Reflect};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
-mod geo;
+pub mod columnar;
+pub mod diversity;
+pub mod error;
+pub mod geo;
+pub mod regions;
+pub mod search_preprocess;
+pub mod terms;
+pub mod text;
+
+use diversity::{DiversityConfig, DiversityItem, DiversityKey};
+use error::{from_js, to_js, to_js_with_nulls, ScorerError};
+use regions::RegionMatch;
+use search_preprocess::LocationOverrideResult;
+use terms::{compile_terms, count_matches, matches_any, TermMatcher, TermMode};
+use text::normalize_text;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HitInput {
+    pub id: String,
+    /// A missing or `null` score becomes NaN, which ranks after every real score.
+    #[serde(default = "missing_score", deserialize_with = "score_or_nan")]
+    pub score: f64,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub country: Option<String>,
+    pub headline: Option<String>,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    /// Only used by the `domain` diversity key.
+    pub website: Option<String>,
+}
 
-#[derive(Debug, Deserialize)]
-struct HitInput {
-    id: String,
+/// The parts of a hit that scoring reads, borrowed from a [`HitInput`] or a columnar buffer.
+#[derive(Debug, Clone, Copy, Default)]
+struct HitFields<'a> {
     score: f64,
-    city: Option<String>,
-    state: Option<String>,
-    country: Option<String>,
-    headline: Option<String>,
-    title: Option<String>,
-    company: Option<String>,
+    city: Option<&'a str>,
+    state: Option<&'a str>,
+    country: Option<&'a str>,
+    headline: Option<&'a str>,
+    title: Option<&'a str>,
+    company: Option<&'a str>,
+    lat: Option<f64>,
+    lng: Option<f64>,
+    website: Option<&'a str>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ScoreConfig {
-    query_city: Option<String>,
-    query_state: Option<String>,
-    query_country: Option<String>,
-    exact_boost: f64,
-    fuzzy_boost: f64,
-    skip_boosts: bool,
-    penalty_cities: Vec<String>,
-    penalty_terms: Vec<String>,
-    strict_penalty: bool,
-    limit: usize,
-}
-
-#[derive(Debug, Serialize)]
-struct ScoredHit {
-    id: String,
-    score: f64,
+impl HitInput {
+    fn fields(&self) -> HitFields<'_> {
+        HitFields {
+            score: self.score,
+            city: self.city.as_deref(),
+            state: self.state.as_deref(),
+            country: self.country.as_deref(),
+            headline: self.headline.as_deref(),
+            title: self.title.as_deref(),
+            company: self.company.as_deref(),
+            lat: self.lat,
+            lng: self.lng,
+            website: self.website.as_deref(),
+        }
+    }
 }
 
-fn lower_non_empty(value: &Option<String>) -> Option<String> {
-    value
-        .as_ref()
-        .map(|v| v.to_lowercase())
-        .filter(|v| !v.is_empty())
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScoreConfig {
+    pub query_city: Option<String>,
+    pub query_state: Option<String>,
+    pub query_country: Option<String>,
+    pub exact_boost: f64,
+    /// Boost for a hit whose state resolves to the query's region (see `regions`).
+    pub fuzzy_boost: f64,
+    pub skip_boosts: bool,
+    pub penalty_cities: Vec<String>,
+    pub penalty_terms: Vec<String>,
+    pub strict_penalty: bool,
+    pub limit: usize,
+    /// Attach a `ScoreExplanation` to every returned hit.
+    #[serde(default)]
+    pub explain: bool,
+    /// Penalty for a hit whose city is in `penalty_cities`. Defaults to 0.2.
+    #[serde(default)]
+    pub penalty_city_weight: Option<f64>,
+    /// Penalty for a word-boundary `penalty_terms` match. Defaults to 0.6 strict / 0.35 lenient.
+    #[serde(default)]
+    pub penalty_term_exact_weight: Option<f64>,
+    /// Penalty for a substring-only `penalty_terms` match. Defaults to 0.35 strict / 0.2 lenient.
+    #[serde(default)]
+    pub penalty_term_substring_weight: Option<f64>,
+    /// Per-term overrides keyed by penalty term (case-insensitive).
+    #[serde(default)]
+    pub penalty_term_weights: HashMap<String, PenaltyTermWeight>,
+    /// Upper bound on the total penalty subtracted from a single hit.
+    #[serde(default)]
+    pub max_penalty: Option<f64>,
+    /// Penalties scoped to specific hit fields. `penalty_terms` keeps matching the joined
+    /// `headline title company` text.
+    #[serde(default)]
+    pub penalty_rules: Vec<PenaltyRule>,
+    /// Query point for the distance boost; hits without `lat`/`lng` get no distance boost.
+    #[serde(default)]
+    pub query_lat: Option<f64>,
+    #[serde(default)]
+    pub query_lng: Option<f64>,
+    /// Boost for a hit at the query point, decaying with distance along `distance_curve`.
+    #[serde(default)]
+    pub distance_boost: f64,
+    /// Distance (km) at which the curve reaches zero (linear), its standard deviation
+    /// (gaussian) or its cut-off (step). Defaults to 50 km.
+    #[serde(default)]
+    pub distance_scale_km: Option<f64>,
+    #[serde(default)]
+    pub distance_curve: DistanceCurve,
+    /// Boost for the query state spelled differently ("CA" vs "California"). Defaults to
+    /// `exact_boost`.
+    #[serde(default)]
+    pub alias_boost: Option<f64>,
+    /// Boost for a hit in a state or province bordering the query's. Defaults to `fuzzy_boost`.
+    #[serde(default)]
+    pub adjacent_boost: Option<f64>,
+    /// MMR reranking applied after scoring and before `limit`.
+    #[serde(default)]
+    pub diversity: Option<DiversityConfig>,
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    /// Seed for `TieBreak::HashedId`.
+    #[serde(default)]
+    pub tie_break_seed: u32,
+    /// Number of ranked hits to skip before the page starts. Ignored when `cursor` is set.
+    #[serde(default)]
+    pub offset: usize,
+    /// `next_cursor` from the previous page; the page starts right after that hit.
+    #[serde(default)]
+    pub cursor: Option<ScoreCursor>,
+}
+
+impl Default for ScoreConfig {
+    fn default() -> Self {
+        ScoreConfig {
+            query_city: None,
+            query_state: None,
+            query_country: None,
+            exact_boost: 0.0,
+            fuzzy_boost: 0.0,
+            skip_boosts: false,
+            penalty_cities: Vec::new(),
+            penalty_terms: Vec::new(),
+            strict_penalty: false,
+            limit: usize::MAX,
+            explain: false,
+            penalty_city_weight: None,
+            penalty_term_exact_weight: None,
+            penalty_term_substring_weight: None,
+            penalty_term_weights: HashMap::new(),
+            max_penalty: None,
+            penalty_rules: Vec::new(),
+            query_lat: None,
+            query_lng: None,
+            distance_boost: 0.0,
+            distance_scale_km: None,
+            distance_curve: DistanceCurve::default(),
+            alias_boost: None,
+            adjacent_boost: None,
+            diversity: None,
+            tie_break: TieBreak::default(),
+            tie_break_seed: 0,
+            offset: 0,
+            cursor: None,
+        }
+    }
+}
+
+fn missing_score() -> f64 {
+    f64::NAN
+}
+
+fn score_or_nan<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+/// How `score_hits` orders hits with equal scores.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TieBreak {
+    /// Keep input order.
+    #[default]
+    OriginalRank,
+    /// Compare ids: as strings, or as numbers for `score_hits_columnar`.
+    Id,
+    /// Compare seeded FNV-1a hashes of the ids: a shuffle that is stable for a given
+    /// `tie_break_seed`. Equal hashes fall back to input order.
+    HashedId,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistanceCurve {
+    #[default]
+    Linear,
+    Gaussian,
+    Step,
+}
+
+const DEFAULT_DISTANCE_SCALE_KM: f64 = 50.0;
+
+impl DistanceCurve {
+    /// Weight in `[0, 1]` for a hit `distance_km` away from the query point.
+    pub fn weight(self, distance_km: f64, scale_km: f64) -> f64 {
+        if !distance_km.is_finite() || !scale_km.is_finite() || scale_km <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            DistanceCurve::Linear => (1.0 - distance_km / scale_km).max(0.0),
+            DistanceCurve::Gaussian => {
+                (-(distance_km * distance_km) / (2.0 * scale_km * scale_km)).exp()
+            }
+            DistanceCurve::Step => {
+                if distance_km <= scale_km {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PenaltyTermWeight {
+    pub exact: Option<f64>,
+    pub substring: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyField {
+    Headline,
+    Title,
+    Company,
+    /// `headline title company` joined with spaces, as used by `penalty_terms`.
+    TextBlob,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PenaltyRule {
+    pub term: String,
+    /// Fields to check; empty means `text_blob`. A rule applies at most once per hit.
+    #[serde(default)]
+    pub fields: Vec<PenaltyField>,
+    /// Penalty for a word-boundary match. Defaults to the config's exact weight.
+    #[serde(default)]
+    pub weight: Option<f64>,
+    /// Penalty for a substring-only match. Defaults to the config's substring weight.
+    #[serde(default)]
+    pub substring_weight: Option<f64>,
+}
+
+impl ScoreConfig {
+    /// Applies the output of `apply_hardcoded_location_overrides_impl`: the overridden
+    /// city/state/country become the query location and the penalty lists are appended.
+    pub fn with_location_overrides(mut self, result: &LocationOverrideResult) -> Self {
+        self.query_city = result.overrides.city.clone();
+        self.query_state = result.overrides.state.clone();
+        self.query_country = result.overrides.country.clone();
+        self.penalty_cities
+            .extend(result.penalty_cities.iter().cloned());
+        self.penalty_terms
+            .extend(result.penalty_terms.iter().cloned());
+        self.strict_penalty |= result.strict_penalty;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredHit {
+    pub id: String,
+    pub score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreExplanation>,
+}
+
+/// Position after the last hit of a page: its final score and id. Crosses the WASM boundary
+/// as an opaque string (`<score bits in hex>:<id>`), so the score round-trips exactly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ScoreCursor {
+    pub score: f64,
+    pub id: String,
+}
+
+impl From<ScoreCursor> for String {
+    fn from(cursor: ScoreCursor) -> Self {
+        format!("{:016x}:{}", cursor.score.to_bits(), cursor.id)
+    }
+}
+
+impl TryFrom<String> for ScoreCursor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let malformed = || format!("invalid cursor {value:?}");
+        let (bits, id) = value.split_once(':').ok_or_else(malformed)?;
+        let bits = u64::from_str_radix(bits, 16).map_err(|_| malformed())?;
+        Ok(Self {
+            score: f64::from_bits(bits),
+            id: id.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredPage {
+    pub hits: Vec<ScoredHit>,
+    /// Set when more hits follow this page.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<ScoreCursor>,
+    /// Number of hits ranked across all pages, after diversity caps.
+    pub total: usize,
 }
 
-fn first_two_chars(value: &str) -> String {
-    value.chars().take(2).collect()
+/// Breakdown of a hit's final score: `base_score + location_boost - penalty`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScoreExplanation {
+    pub base_score: f64,
+    pub exact_state_boost: f64,
+    pub alias_state_boost: f64,
+    pub adjacent_state_boost: f64,
+    pub exact_city_boost: f64,
+    pub exact_country_boost: f64,
+    pub fuzzy_state_boost: f64,
+    /// Great-circle distance from the query point, when both coordinates are known.
+    pub distance_km: Option<f64>,
+    pub distance_boost: f64,
+    pub location_boost: f64,
+    /// The `penalty_cities` entry that matched the hit city.
+    pub penalty_city: Option<String>,
+    pub penalty_city_amount: f64,
+    pub penalty_terms: Vec<PenaltyTermMatch>,
+    pub penalty: f64,
+    /// Set when `max_penalty` clamped `penalty`.
+    pub penalty_capped: bool,
 }
 
-fn to_js_error(prefix: &str, err: impl std::fmt::Display) -> JsValue {
-    JsValue::from_str(&format!("{prefix}: {err}"))
+#[derive(Debug, Clone, Serialize)]
+pub struct PenaltyTermMatch {
+    pub term: String,
+    pub field: PenaltyField,
+    /// `true` for a word-boundary match, `false` for a plain substring match.
+    pub exact: bool,
+    pub penalty: f64,
+}
+
+fn normalized_non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_ref()
+        .map(|v| normalize_text(v))
+        .filter(|v| !v.is_empty())
 }
 
 #[wasm_bindgen]
@@ -69,24 +382,6 @@ fn js_to_string(value: &JsValue) -> String {
     js_string(value).as_string().unwrap_or_default()
 }
 
-fn normalize_terms(terms: &[String]) -> Vec<String> {
-    terms
-        .iter()
-        .map(|term| term.to_lowercase())
-        .map(|term| term.trim().to_string())
-        .filter(|term| !term.is_empty())
-        .collect()
-}
-
-fn contains_any_lc(text_lc: &str, terms: &[String]) -> bool {
-    if text_lc.is_empty() || terms.is_empty() {
-        return false;
-    }
-    terms
-        .iter()
-        .any(|term| !term.is_empty() && text_lc.contains(term))
-}
-
 fn is_js_truthy(value: &JsValue) -> bool {
     if is_nullish(value) {
         return false;
@@ -168,55 +463,378 @@ fn match_id_or_empty(match_obj: &JsValue) -> String {
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct PostTrainingProfile {
-    active: bool,
+/// Mirrors `PostTrainingProfile` in `postTraining.ts`. Unknown fields are rejected so the two
+/// definitions cannot drift apart silently.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostTrainingProfile {
+    pub active: bool,
     #[serde(rename = "excludeTerms", default)]
-    exclude_terms: Vec<String>,
+    pub exclude_terms: Vec<String>,
     #[serde(rename = "demoteTerms", default)]
-    demote_terms: Vec<String>,
+    pub demote_terms: Vec<String>,
+    /// Lenient exclusion (the default) checks company, title and headline. Strict exclusion also
+    /// drops matches whose website or company industry contains an exclude term.
+    #[serde(rename = "strictExclude", default)]
+    pub strict_exclude: Option<bool>,
     #[serde(rename = "requirePositive", default)]
-    require_positive: Option<bool>,
+    pub require_positive: Option<bool>,
     #[serde(rename = "includeCompanyTerms", default)]
-    include_company_terms: Vec<String>,
+    pub include_company_terms: Vec<String>,
     #[serde(rename = "includeTitleTerms", default)]
-    include_title_terms: Vec<String>,
+    pub include_title_terms: Vec<String>,
     #[serde(rename = "includeWebsiteTerms", default)]
-    include_website_terms: Vec<String>,
+    pub include_website_terms: Vec<String>,
     #[serde(rename = "includeIndustryTerms", default)]
-    include_industry_terms: Vec<String>,
+    pub include_industry_terms: Vec<String>,
     #[serde(rename = "auxCompanyTerms", default)]
-    aux_company_terms: Vec<String>,
+    pub aux_company_terms: Vec<String>,
     #[serde(rename = "auxTitleTerms", default)]
-    aux_title_terms: Vec<String>,
+    pub aux_title_terms: Vec<String>,
     #[serde(rename = "auxWebsiteTerms", default)]
-    aux_website_terms: Vec<String>,
+    pub aux_website_terms: Vec<String>,
     #[serde(rename = "auxIndustryTerms", default)]
-    aux_industry_terms: Vec<String>,
+    pub aux_industry_terms: Vec<String>,
+    /// Mode for terms without explicit syntax; see [`terms`]. Defaults to whole-word matching.
+    #[serde(rename = "termMatch", default)]
+    pub term_match: TermMode,
+    /// Switches from fixed tiers to graded ranking when present.
+    #[serde(default)]
+    pub scoring: Option<PostTrainingScoring>,
+    /// Duplicate detection; defaults to one result per contact in every mode.
+    #[serde(default)]
+    pub dedupe: Option<PostTrainingDedupe>,
+    /// MMR reranking applied after ranking and dedupe, before `finalLimit`.
+    #[serde(default)]
+    pub diversity: Option<DiversityConfig>,
 }
 
+/// Two matches are duplicates when they share any of `keys`; duplicates chain, so A~B and
+/// B~C put all three in one group whatever order they arrive in. Each group keeps the position
+/// of its best-ranked member, and `keep` decides which member's record fills it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct PostTrainingDedupe {
+    pub keys: Vec<DedupeKey>,
+    pub keep: DedupeKeep,
+}
+
+impl Default for PostTrainingDedupe {
+    fn default() -> Self {
+        Self {
+            keys: vec![DedupeKey::Contact],
+            keep: DedupeKeep::First,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupeKey {
+    /// `contactId`, falling back to the match `id`.
+    Contact,
+    /// Case-insensitive email address.
+    Email,
+    /// Normalised company together with normalised first and last name.
+    CompanyName,
+    /// Website host without `www.`; shared hosts such as facebook.com are ignored.
+    Domain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupeKeep {
+    /// The best-ranked duplicate.
+    #[default]
+    First,
+    /// The duplicate with the highest Elasticsearch `score`.
+    HighestScore,
+    /// The duplicate with the most non-empty contact fields.
+    MostComplete,
+}
+
+/// Graded post-training: each match gets a score in (-1, 1) from the include, aux and demote
+/// terms it hits, weighted by field, which is then blended with its Elasticsearch `score`
+/// (normalised against the best score in the batch). Matches are ranked by the blend, so a
+/// strong aux match can outrank a weak positive one.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields, default)]
+pub struct PostTrainingScoring {
+    /// Share of the post-training score in the blend; the ES score gets `1 - weight`.
+    pub weight: f64,
+    /// Added per include term matched, scaled by the field weight.
+    pub include_weight: f64,
+    /// Added per aux term matched, scaled by the field weight.
+    pub aux_weight: f64,
+    /// Subtracted per demote term matched, scaled by the field weight.
+    pub demote_weight: f64,
+    pub field_weights: PostTrainingFieldWeights,
+}
+
+impl Default for PostTrainingScoring {
+    fn default() -> Self {
+        Self {
+            weight: 0.5,
+            include_weight: 1.0,
+            aux_weight: 0.4,
+            demote_weight: 0.5,
+            field_weights: PostTrainingFieldWeights::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct PostTrainingFieldWeights {
+    pub company: f64,
+    pub title: f64,
+    pub headline: f64,
+    pub website: f64,
+    pub industry: f64,
+    pub metadata: f64,
+}
+
+impl Default for PostTrainingFieldWeights {
+    fn default() -> Self {
+        Self {
+            company: 1.0,
+            title: 1.0,
+            headline: 0.75,
+            website: 0.5,
+            industry: 0.5,
+            metadata: 0.25,
+        }
+    }
+}
+
+impl PostTrainingFieldWeights {
+    fn get(&self, field: PostTrainingField) -> f64 {
+        match field {
+            PostTrainingField::Company => self.company,
+            PostTrainingField::Title => self.title,
+            PostTrainingField::Headline => self.headline,
+            PostTrainingField::Website => self.website,
+            PostTrainingField::Industry => self.industry,
+            PostTrainingField::Metadata => self.metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostTrainingField {
+    Company,
+    Title,
+    Headline,
+    Website,
+    Industry,
+    Metadata,
+}
+
+/// Normalised text of each field a post-training term can match.
+#[derive(Debug, Default)]
+struct MatchTexts {
+    company: String,
+    title: String,
+    headline: String,
+    website: String,
+    industry: String,
+    metadata: String,
+}
+
+type FieldTerms<'a> = (PostTrainingField, &'a str, &'a [TermMatcher]);
+
 #[derive(Debug)]
 struct PostTrainingTerms {
-    exclude_terms: Vec<String>,
-    demote_terms: Vec<String>,
-    include_company_terms: Vec<String>,
-    include_title_terms: Vec<String>,
-    include_website_terms: Vec<String>,
-    include_industry_terms: Vec<String>,
-    include_company_or_title_terms: Vec<String>,
-    aux_company_terms: Vec<String>,
-    aux_title_terms: Vec<String>,
-    aux_website_terms: Vec<String>,
-    aux_industry_terms: Vec<String>,
-    aux_company_or_title_terms: Vec<String>,
+    exclude_terms: Vec<TermMatcher>,
+    demote_terms: Vec<TermMatcher>,
+    include_company_terms: Vec<TermMatcher>,
+    include_title_terms: Vec<TermMatcher>,
+    include_website_terms: Vec<TermMatcher>,
+    include_industry_terms: Vec<TermMatcher>,
+    include_company_or_title_terms: Vec<TermMatcher>,
+    aux_company_terms: Vec<TermMatcher>,
+    aux_title_terms: Vec<TermMatcher>,
+    aux_website_terms: Vec<TermMatcher>,
+    aux_industry_terms: Vec<TermMatcher>,
+    aux_company_or_title_terms: Vec<TermMatcher>,
+    strict_exclude: bool,
     require_positive: bool,
 }
 
+impl PostTrainingTerms {
+    /// Website and industry only count under `strictExclude`.
+    fn exclude_fields<'a>(&'a self, texts: &'a MatchTexts) -> [FieldTerms<'a>; 5] {
+        let strict_terms: &[TermMatcher] = if self.strict_exclude {
+            &self.exclude_terms
+        } else {
+            &[]
+        };
+        [
+            (
+                PostTrainingField::Company,
+                &texts.company,
+                &self.exclude_terms,
+            ),
+            (PostTrainingField::Title, &texts.title, &self.exclude_terms),
+            (
+                PostTrainingField::Headline,
+                &texts.headline,
+                &self.exclude_terms,
+            ),
+            (PostTrainingField::Website, &texts.website, strict_terms),
+            (PostTrainingField::Industry, &texts.industry, strict_terms),
+        ]
+    }
+
+    fn include_fields<'a>(&'a self, texts: &'a MatchTexts) -> [FieldTerms<'a>; 6] {
+        [
+            (
+                PostTrainingField::Company,
+                &texts.company,
+                &self.include_company_terms,
+            ),
+            (
+                PostTrainingField::Title,
+                &texts.title,
+                &self.include_title_terms,
+            ),
+            (
+                PostTrainingField::Headline,
+                &texts.headline,
+                &self.include_company_or_title_terms,
+            ),
+            (
+                PostTrainingField::Website,
+                &texts.website,
+                &self.include_website_terms,
+            ),
+            (
+                PostTrainingField::Industry,
+                &texts.industry,
+                &self.include_industry_terms,
+            ),
+            (
+                PostTrainingField::Metadata,
+                &texts.metadata,
+                &self.include_company_or_title_terms,
+            ),
+        ]
+    }
+
+    fn aux_fields<'a>(&'a self, texts: &'a MatchTexts) -> [FieldTerms<'a>; 6] {
+        [
+            (
+                PostTrainingField::Company,
+                &texts.company,
+                &self.aux_company_terms,
+            ),
+            (
+                PostTrainingField::Title,
+                &texts.title,
+                &self.aux_title_terms,
+            ),
+            (
+                PostTrainingField::Headline,
+                &texts.headline,
+                &self.aux_company_or_title_terms,
+            ),
+            (
+                PostTrainingField::Website,
+                &texts.website,
+                &self.aux_website_terms,
+            ),
+            (
+                PostTrainingField::Industry,
+                &texts.industry,
+                &self.aux_industry_terms,
+            ),
+            (
+                PostTrainingField::Metadata,
+                &texts.metadata,
+                &self.aux_company_or_title_terms,
+            ),
+        ]
+    }
+
+    fn demote_fields<'a>(&'a self, texts: &'a MatchTexts) -> [FieldTerms<'a>; 3] {
+        [
+            (
+                PostTrainingField::Company,
+                &texts.company,
+                &self.demote_terms,
+            ),
+            (PostTrainingField::Title, &texts.title, &self.demote_terms),
+            (
+                PostTrainingField::Headline,
+                &texts.headline,
+                &self.demote_terms,
+            ),
+        ]
+    }
+}
+
+fn any_field_matches(fields: &[FieldTerms]) -> bool {
+    fields
+        .iter()
+        .any(|(_, text, terms)| matches_any(text, terms))
+}
+
+fn first_field_match<'a>(
+    fields: &[FieldTerms<'a>],
+) -> Option<(PostTrainingField, &'a TermMatcher)> {
+    fields.iter().find_map(|(field, text, terms)| {
+        terms
+            .iter()
+            .find(|term| term.is_match(text))
+            .map(|term| (*field, term))
+    })
+}
+
+fn collect_term_hits(terms: &PostTrainingTerms, texts: &MatchTexts) -> Vec<PostTrainingTermHit> {
+    let mut hits = Vec::new();
+    let mut push_hits = |kind: PostTrainingTermKind, fields: &[FieldTerms]| {
+        for (field, text, terms) in fields {
+            for term in terms.iter().filter(|term| term.is_match(text)) {
+                hits.push(PostTrainingTermHit {
+                    term: term.term.clone(),
+                    field: *field,
+                    kind,
+                });
+            }
+        }
+    };
+    push_hits(PostTrainingTermKind::Include, &terms.include_fields(texts));
+    push_hits(PostTrainingTermKind::Aux, &terms.aux_fields(texts));
+    push_hits(PostTrainingTermKind::Demote, &terms.demote_fields(texts));
+    hits
+}
+
+fn graded_post_training_score(
+    terms: &PostTrainingTerms,
+    scoring: &PostTrainingScoring,
+    texts: &MatchTexts,
+) -> f64 {
+    let weighted_hits = |fields: &[FieldTerms]| -> f64 {
+        fields
+            .iter()
+            .map(|(field, text, terms)| {
+                scoring.field_weights.get(*field) * count_matches(text, terms) as f64
+            })
+            .sum()
+    };
+    let raw = scoring.include_weight * weighted_hits(&terms.include_fields(texts))
+        + scoring.aux_weight * weighted_hits(&terms.aux_fields(texts))
+        - scoring.demote_weight * weighted_hits(&terms.demote_fields(texts));
+    raw / (1.0 + raw.abs())
+}
+
 fn build_post_training_terms(profile: &PostTrainingProfile) -> PostTrainingTerms {
-    let include_company_terms = normalize_terms(&profile.include_company_terms);
-    let include_title_terms = normalize_terms(&profile.include_title_terms);
-    let aux_company_terms = normalize_terms(&profile.aux_company_terms);
-    let aux_title_terms = normalize_terms(&profile.aux_title_terms);
+    let mode = profile.term_match;
+    let include_company_terms = compile_terms(&profile.include_company_terms, mode);
+    let include_title_terms = compile_terms(&profile.include_title_terms, mode);
+    let aux_company_terms = compile_terms(&profile.aux_company_terms, mode);
+    let aux_title_terms = compile_terms(&profile.aux_title_terms, mode);
 
     let mut include_company_or_title_terms = Vec::with_capacity(
         include_company_terms.len().saturating_add(include_title_terms.len()),
@@ -230,311 +848,1759 @@ fn build_post_training_terms(profile: &PostTrainingProfile) -> PostTrainingTerms
     aux_company_or_title_terms.extend(aux_title_terms.iter().cloned());
 
     PostTrainingTerms {
-        exclude_terms: normalize_terms(&profile.exclude_terms),
-        demote_terms: normalize_terms(&profile.demote_terms),
+        exclude_terms: compile_terms(&profile.exclude_terms, mode),
+        demote_terms: compile_terms(&profile.demote_terms, mode),
         include_company_terms,
         include_title_terms,
-        include_website_terms: normalize_terms(&profile.include_website_terms),
-        include_industry_terms: normalize_terms(&profile.include_industry_terms),
+        include_website_terms: compile_terms(&profile.include_website_terms, mode),
+        include_industry_terms: compile_terms(&profile.include_industry_terms, mode),
         include_company_or_title_terms,
         aux_company_terms,
         aux_title_terms,
-        aux_website_terms: normalize_terms(&profile.aux_website_terms),
-        aux_industry_terms: normalize_terms(&profile.aux_industry_terms),
+        aux_website_terms: compile_terms(&profile.aux_website_terms, mode),
+        aux_industry_terms: compile_terms(&profile.aux_industry_terms, mode),
         aux_company_or_title_terms,
+        strict_exclude: profile.strict_exclude.unwrap_or(false),
         require_positive: profile.require_positive.unwrap_or(false),
     }
 }
 
+/// Fields of an Elasticsearch match that post-training reads. The WASM adapter fills these from
+/// `match.id` and `match.metadata`; `id` is empty when the JS value was falsy.
+#[derive(Debug, Clone, Default)]
+pub struct EsMatchInput {
+    pub id: String,
+    pub contact_id: Option<String>,
+    pub company: Option<String>,
+    pub title: Option<String>,
+    pub headline: Option<String>,
+    pub website: Option<String>,
+    pub company_industry: Option<String>,
+    pub metadata: Option<String>,
+    pub email: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    /// Elasticsearch relevance score; read by graded post-training and `highest_score` dedupe.
+    pub score: Option<f64>,
+}
+
 #[derive(Debug)]
 struct ClassifiedMatch {
-    js_match: JsValue,
-    key: String,
+    index: usize,
     positive: bool,
     aux: bool,
     demoted_positive: bool,
+    post_training_score: f64,
+    matched_terms: Vec<PostTrainingTermHit>,
 }
 
-fn push_classified_if_new(
-    entry: &ClassifiedMatch,
-    seen: &mut HashSet<String>,
-    ordered: &mut Vec<JsValue>,
-) {
-    if entry.key.is_empty() {
-        return;
+impl ClassifiedMatch {
+    fn tier(&self) -> PostTrainingTier {
+        if self.demoted_positive {
+            PostTrainingTier::DemotedPositive
+        } else if self.positive {
+            PostTrainingTier::Positive
+        } else if self.aux {
+            PostTrainingTier::Aux
+        } else {
+            PostTrainingTier::Other
+        }
     }
-    if seen.contains(&entry.key) {
-        return;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostTrainingTier {
+    Positive,
+    DemotedPositive,
+    Aux,
+    /// No include or aux term matched; always the case for inactive profiles.
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostTrainingTermKind {
+    Include,
+    Aux,
+    Demote,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PostTrainingTermHit {
+    pub term: String,
+    pub field: PostTrainingField,
+    pub kind: PostTrainingTermKind,
+}
+
+/// Why one returned match landed where it did. Parallel to [`PostTrainingReport::indices`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PostTrainingClassification {
+    pub index: usize,
+    pub tier: PostTrainingTier,
+    pub matched_terms: Vec<PostTrainingTermHit>,
+    /// Graded post-training score, when the profile has `scoring`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_training_score: Option<f64>,
+}
+
+/// The first exclude term that removed a match.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostTrainingExclusion {
+    pub index: usize,
+    pub term: String,
+    pub field: PostTrainingField,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PostTrainingSummary {
+    pub total: usize,
+    pub excluded: usize,
+    /// Matches merged into a better-placed duplicate under the profile's dedupe keys.
+    pub deduplicated: usize,
+    /// Matches dropped under `requirePositive` because they had neither a `contactId` nor an
+    /// `id`.
+    pub missing_key: usize,
+    pub returned: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PostTrainingReport {
+    pub indices: Vec<usize>,
+    pub classifications: Vec<PostTrainingClassification>,
+    pub exclusions: Vec<PostTrainingExclusion>,
+    pub summary: PostTrainingSummary,
+}
+
+fn contact_key(es_match: &EsMatchInput) -> &str {
+    es_match
+        .contact_id
+        .as_deref()
+        .filter(|value| !value.is_empty())
+        .unwrap_or(&es_match.id)
+}
+
+fn dedupe_key(es_match: &EsMatchInput, key: DedupeKey) -> Option<String> {
+    match key {
+        DedupeKey::Contact => Some(contact_key(es_match).to_string()).filter(|key| !key.is_empty()),
+        DedupeKey::Email => es_match
+            .email
+            .as_deref()
+            .map(|email| email.trim().to_lowercase())
+            .filter(|email| email.contains('@')),
+        DedupeKey::CompanyName => {
+            let company = normalize_text(es_match.company.as_deref().unwrap_or_default());
+            let name = normalize_text(&format!(
+                "{} {}",
+                es_match.first_name.as_deref().unwrap_or_default(),
+                es_match.last_name.as_deref().unwrap_or_default()
+            ));
+            (!company.is_empty() && !name.is_empty()).then(|| format!("{company}|{name}"))
+        }
+        DedupeKey::Domain => es_match.website.as_deref().and_then(text::website_domain),
     }
-    seen.insert(entry.key.clone());
-    ordered.push(entry.js_match.clone());
 }
 
-#[wasm_bindgen]
-pub fn score_hits(hits: JsValue, config: JsValue) -> Result<JsValue, JsValue> {
-    let hits: Vec<HitInput> = serde_wasm_bindgen::from_value(hits)
-        .map_err(|err| to_js_error("invalid hits payload", err))?;
-    let config: ScoreConfig = serde_wasm_bindgen::from_value(config)
-        .map_err(|err| to_js_error("invalid config payload", err))?;
+fn completeness(es_match: &EsMatchInput) -> usize {
+    [
+        &es_match.email,
+        &es_match.first_name,
+        &es_match.last_name,
+        &es_match.company,
+        &es_match.title,
+        &es_match.headline,
+        &es_match.website,
+        &es_match.company_industry,
+    ]
+    .iter()
+    .filter(|value| {
+        value
+            .as_deref()
+            .is_some_and(|value| !value.trim().is_empty())
+    })
+    .count()
+}
+
+impl DedupeKeep {
+    fn prefers(self, candidate: &EsMatchInput, current: &EsMatchInput) -> bool {
+        match self {
+            DedupeKeep::First => false,
+            DedupeKeep::HighestScore => {
+                let score = |es_match: &EsMatchInput| {
+                    es_match
+                        .score
+                        .filter(|score| !score.is_nan())
+                        .unwrap_or(f64::NEG_INFINITY)
+                };
+                score(candidate) > score(current)
+            }
+            DedupeKeep::MostComplete => completeness(candidate) > completeness(current),
+        }
+    }
+}
+
+/// Collapses duplicates in `ranked`. Under `requirePositive`, matches without a contact key
+/// are dropped as well, as the tiered path always has.
+fn dedupe_ranked(
+    matches: &[EsMatchInput],
+    ranked: Vec<usize>,
+    dedupe: &PostTrainingDedupe,
+    drop_missing_key: bool,
+    summary: &mut PostTrainingSummary,
+) -> Vec<usize> {
+    // Union-find over groups. Groups are created in rank order, so the root of a merged group
+    // is its smallest id, which is also its best-ranked member's position.
+    let mut group_by_key: HashMap<(DedupeKey, String), usize> = HashMap::new();
+    let mut parent: Vec<usize> = Vec::with_capacity(ranked.len());
+    let mut winners: Vec<usize> = Vec::with_capacity(ranked.len());
+    let mut considered = 0;
+
+    for index in ranked {
+        let es_match = &matches[index];
+        if drop_missing_key && contact_key(es_match).is_empty() {
+            summary.missing_key += 1;
+            continue;
+        }
+        considered += 1;
+
+        let keys: Vec<(DedupeKey, String)> = dedupe
+            .keys
+            .iter()
+            .filter_map(|&key| dedupe_key(es_match, key).map(|value| (key, value)))
+            .collect();
+        let mut roots: Vec<usize> = keys
+            .iter()
+            .filter_map(|key| group_by_key.get(key).copied())
+            .map(|group| find_group(&mut parent, group))
+            .collect();
+        roots.sort_unstable();
+        roots.dedup();
+
+        let group = match roots.split_first() {
+            Some((&root, others)) => {
+                for &other in others {
+                    parent[other] = root;
+                    let (candidate, current) = (&matches[winners[other]], &matches[winners[root]]);
+                    if dedupe.keep.prefers(candidate, current) {
+                        winners[root] = winners[other];
+                    }
+                }
+                if dedupe.keep.prefers(es_match, &matches[winners[root]]) {
+                    winners[root] = index;
+                }
+                root
+            }
+            None => {
+                parent.push(parent.len());
+                winners.push(index);
+                winners.len() - 1
+            }
+        };
+        for key in keys {
+            group_by_key.entry(key).or_insert(group);
+        }
+    }
+
+    let roots: Vec<usize> = (0..parent.len())
+        .filter(|&group| parent[group] == group)
+        .collect();
+    summary.deduplicated += considered - roots.len();
+    roots.into_iter().map(|group| winners[group]).collect()
+}
+
+fn find_group(parent: &mut [usize], mut group: usize) -> usize {
+    while parent[group] != group {
+        parent[group] = parent[parent[group]];
+        group = parent[group];
+    }
+    group
+}
+
+struct CompiledPenaltyTerm {
+    term: String,
+    fields: Vec<PenaltyField>,
+    exact_regex: Regex,
+    exact_weight: f64,
+    substring_weight: f64,
+}
 
+struct ScoringContext {
+    penalty_cities: HashSet<String>,
+    penalty_city_weight: f64,
+    penalty_terms: Vec<CompiledPenaltyTerm>,
+    query_city: Option<String>,
+    query_state: Option<String>,
+    query_country: Option<String>,
+    query_point: Option<(f64, f64)>,
+    distance_scale_km: f64,
+}
+
+fn build_scoring_context(config: &ScoreConfig) -> ScoringContext {
     let penalty_cities: HashSet<String> = config
         .penalty_cities
         .iter()
-        .map(|city| city.to_lowercase())
+        .map(|city| normalize_text(city))
         .collect();
-    let penalty_terms: HashSet<String> = config
-        .penalty_terms
+
+    let default_exact_weight = config
+        .penalty_term_exact_weight
+        .unwrap_or(if config.strict_penalty { 0.6 } else { 0.35 });
+    let default_substring_weight = config
+        .penalty_term_substring_weight
+        .unwrap_or(if config.strict_penalty { 0.35 } else { 0.2 });
+    let term_weights: HashMap<String, &PenaltyTermWeight> = config
+        .penalty_term_weights
         .iter()
-        .map(|term| term.to_lowercase())
+        .map(|(term, weight)| (normalize_text(term), weight))
         .collect();
 
-    let compiled_penalty_terms: Vec<(String, Regex)> = penalty_terms
+    // Dedupe while keeping caller order so explanations list terms deterministically.
+    let mut seen_terms: HashSet<String> = HashSet::new();
+    let mut penalty_terms: Vec<CompiledPenaltyTerm> = config
+        .penalty_terms
         .iter()
-        .filter(|term| !term.is_empty())
+        .map(|term| normalize_text(term))
+        .filter(|term| !term.is_empty() && seen_terms.insert(term.clone()))
         .filter_map(|term| {
-            let pattern = format!("(^|\\b){}(\\b|$)", regex::escape(term));
-            Regex::new(&pattern).ok().map(|regex| (term.clone(), regex))
+            let exact_regex = terms::word_boundary_regex(&term)?;
+            let weight = term_weights.get(&term);
+            Some(CompiledPenaltyTerm {
+                exact_weight: weight.and_then(|w| w.exact).unwrap_or(default_exact_weight),
+                substring_weight: weight
+                    .and_then(|w| w.substring)
+                    .unwrap_or(default_substring_weight),
+                fields: vec![PenaltyField::TextBlob],
+                term,
+                exact_regex,
+            })
         })
         .collect();
 
-    let query_city = lower_non_empty(&config.query_city);
-    let query_state = lower_non_empty(&config.query_state);
-    let query_country = lower_non_empty(&config.query_country);
-
-    let mut scored_hits = hits
-        .into_iter()
-        .map(|hit| {
-            let mut location_boost = 0.0;
-
-            let hit_city = hit.city.unwrap_or_default().to_lowercase();
-            let hit_state = hit.state.unwrap_or_default().to_lowercase();
-            let hit_country = hit.country.unwrap_or_default().to_lowercase();
+    penalty_terms.extend(config.penalty_rules.iter().filter_map(|rule| {
+        let term = normalize_text(&rule.term);
+        if term.is_empty() {
+            return None;
+        }
+        let fields = if rule.fields.is_empty() {
+            vec![PenaltyField::TextBlob]
+        } else {
+            rule.fields.clone()
+        };
+        Some(CompiledPenaltyTerm {
+            exact_regex: terms::word_boundary_regex(&term)?,
+            exact_weight: rule.weight.unwrap_or(default_exact_weight),
+            substring_weight: rule.substring_weight.unwrap_or(default_substring_weight),
+            fields,
+            term,
+        })
+    }));
+
+    ScoringContext {
+        penalty_cities,
+        penalty_city_weight: config.penalty_city_weight.unwrap_or(0.2),
+        penalty_terms,
+        query_city: normalized_non_empty(&config.query_city),
+        query_state: normalized_non_empty(&config.query_state),
+        query_country: normalized_non_empty(&config.query_country),
+        query_point: config
+            .query_lat
+            .zip(config.query_lng)
+            .filter(|(lat, lng)| lat.is_finite() && lng.is_finite()),
+        distance_scale_km: config
+            .distance_scale_km
+            .unwrap_or(DEFAULT_DISTANCE_SCALE_KM),
+    }
+}
 
-            if !config.skip_boosts {
-                if let Some(expected_state) = &query_state {
-                    if hit_state == *expected_state {
-                        location_boost += config.exact_boost;
-                    }
+fn explain_hit(hit: &HitFields, config: &ScoreConfig, ctx: &ScoringContext) -> ScoreExplanation {
+    let mut explanation = ScoreExplanation {
+        base_score: hit.score,
+        ..ScoreExplanation::default()
+    };
+
+    let hit_city = normalize_text(hit.city.unwrap_or_default());
+    let hit_state = normalize_text(hit.state.unwrap_or_default());
+    let hit_country = normalize_text(hit.country.unwrap_or_default());
+
+    if !config.skip_boosts {
+        if let Some(expected_state) = &ctx.query_state {
+            match regions::match_regions(expected_state, &hit_state) {
+                RegionMatch::Exact => {
+                    explanation.exact_state_boost = config.exact_boost;
+                    explanation.fuzzy_state_boost = config.fuzzy_boost;
                 }
-
-                if let Some(expected_city) = &query_city {
-                    if hit_city == *expected_city {
-                        location_boost += config.exact_boost;
-                    }
+                RegionMatch::Alias => {
+                    explanation.alias_state_boost =
+                        config.alias_boost.unwrap_or(config.exact_boost);
+                    explanation.fuzzy_state_boost = config.fuzzy_boost;
                 }
-
-                if let Some(expected_country) = &query_country {
-                    if hit_country == *expected_country {
-                        location_boost += config.exact_boost;
-                    }
+                RegionMatch::Adjacent => {
+                    explanation.adjacent_state_boost =
+                        config.adjacent_boost.unwrap_or(config.fuzzy_boost);
                 }
+                RegionMatch::None => {}
+            }
+        }
 
-                if let Some(expected_state) = &query_state {
-                    if !hit_state.is_empty() {
-                        let query_prefix = first_two_chars(expected_state);
-                        let hit_prefix = first_two_chars(&hit_state);
+        if let Some(expected_city) = &ctx.query_city {
+            if hit_city == *expected_city {
+                explanation.exact_city_boost = config.exact_boost;
+            }
+        }
 
-                        if hit_state.contains(&query_prefix) || expected_state.contains(&hit_prefix)
-                        {
-                            location_boost += config.fuzzy_boost;
-                        }
-                    }
-                }
+        if let Some(expected_country) = &ctx.query_country {
+            if hit_country == *expected_country {
+                explanation.exact_country_boost = config.exact_boost;
             }
+        }
 
-            let mut penalty = if penalty_cities.contains(&hit_city) {
-                0.2
-            } else {
-                0.0
-            };
+        let hit_point = hit.lat.zip(hit.lng);
+        if let (Some((query_lat, query_lng)), Some((lat, lng))) = (ctx.query_point, hit_point) {
+            let distance_km = geo::haversine_km_impl(query_lat, query_lng, lat, lng);
+            if distance_km.is_finite() {
+                explanation.distance_km = Some(distance_km);
+                explanation.distance_boost = config.distance_boost
+                    * config
+                        .distance_curve
+                        .weight(distance_km, ctx.distance_scale_km);
+            }
+        }
+    }
+    explanation.location_boost = explanation.exact_state_boost
+        + explanation.alias_state_boost
+        + explanation.adjacent_state_boost
+        + explanation.exact_city_boost
+        + explanation.exact_country_boost
+        + explanation.fuzzy_state_boost
+        + explanation.distance_boost;
+
+    if ctx.penalty_cities.contains(&hit_city) {
+        explanation.penalty_city = Some(hit_city);
+        explanation.penalty_city_amount = ctx.penalty_city_weight;
+    }
+    let mut penalty = explanation.penalty_city_amount;
+
+    let headline = normalize_text(hit.headline.unwrap_or_default());
+    let title = normalize_text(hit.title.unwrap_or_default());
+    let company = normalize_text(hit.company.unwrap_or_default());
+    let text_blob = format!("{headline} {title} {company}");
+    let field_text = |field: PenaltyField| -> &str {
+        match field {
+            PenaltyField::Headline => &headline,
+            PenaltyField::Title => &title,
+            PenaltyField::Company => &company,
+            PenaltyField::TextBlob => &text_blob,
+        }
+    };
+
+    for penalty_term in &ctx.penalty_terms {
+        // An exact match in any targeted field wins over a substring match in another.
+        let exact_field = penalty_term
+            .fields
+            .iter()
+            .copied()
+            .find(|field| penalty_term.exact_regex.is_match(field_text(*field)));
+        let (field, exact, amount) = if let Some(field) = exact_field {
+            (field, true, penalty_term.exact_weight)
+        } else if let Some(field) = penalty_term
+            .fields
+            .iter()
+            .copied()
+            .find(|field| field_text(*field).contains(&penalty_term.term))
+        {
+            (field, false, penalty_term.substring_weight)
+        } else {
+            continue;
+        };
+        penalty += amount;
+        explanation.penalty_terms.push(PenaltyTermMatch {
+            term: penalty_term.term.clone(),
+            field,
+            exact,
+            penalty: amount,
+        });
+    }
 
-            let headline = hit.headline.unwrap_or_default().to_lowercase();
-            let title = hit.title.unwrap_or_default().to_lowercase();
-            let company = hit.company.unwrap_or_default().to_lowercase();
-            let text_blob = format!("{headline} {title} {company}");
+    if let Some(max_penalty) = config.max_penalty.filter(|max| max.is_finite()) {
+        if penalty > max_penalty {
+            penalty = max_penalty;
+            explanation.penalty_capped = true;
+        }
+    }
+    explanation.penalty = penalty;
 
-            for (term, exact_regex) in &compiled_penalty_terms {
-                if exact_regex.is_match(&text_blob) {
-                    penalty += if config.strict_penalty { 0.6 } else { 0.35 };
-                } else if text_blob.contains(term) {
-                    penalty += if config.strict_penalty { 0.35 } else { 0.2 };
-                }
-            }
+    explanation
+}
 
-            ScoredHit {
-                id: hit.id,
-                score: hit.score + location_boost - penalty,
-            }
+/// Descending by score with NaN after every number.
+fn compare_scores_desc(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Final score of every hit, with the explanations behind them when `config.explain` is set.
+fn score_fields(
+    hits: &[HitFields],
+    config: &ScoreConfig,
+) -> (Vec<f64>, Vec<Option<ScoreExplanation>>) {
+    let ctx = build_scoring_context(config);
+    hits.iter()
+        .map(|hit| {
+            let explanation = explain_hit(hit, config, &ctx);
+            let score = explanation.base_score + explanation.location_boost - explanation.penalty;
+            (score, config.explain.then_some(explanation))
         })
-        .collect::<Vec<_>>();
+        .unzip()
+}
 
-    scored_hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
-    scored_hits.truncate(config.limit);
+/// Hit id compared by `tie_break` and stored in cursors: strings from `score_hits`, numbers from
+/// the columnar entry point. `hashed_id` hashes the decimal form of numeric ids, so both orders
+/// agree for the same ids.
+pub(crate) trait HitId: Copy + Ord {
+    fn hashed(self, seed: u32) -> u32;
+}
 
-    serde_wasm_bindgen::to_value(&scored_hits)
-        .map_err(|err| to_js_error("failed to serialize score output", err))
+impl HitId for &str {
+    fn hashed(self, seed: u32) -> u32 {
+        geo::fnv1a_str(seed, self)
+    }
 }
 
-#[wasm_bindgen]
-pub fn apply_post_training_to_es_matches(
-    matches: JsValue,
-    profile: JsValue,
-    final_limit: JsValue,
-) -> Result<JsValue, JsValue> {
-    let profile: PostTrainingProfile = serde_wasm_bindgen::from_value(profile)
-        .map_err(|err| to_js_error("invalid profile payload", err))?;
-    let final_limit: f64 = serde_wasm_bindgen::from_value(final_limit)
-        .map_err(|err| to_js_error("invalid finalLimit payload", err))?;
+impl HitId for u32 {
+    fn hashed(self, seed: u32) -> u32 {
+        geo::fnv1a_str(seed, &self.to_string())
+    }
+}
 
-    if !Array::is_array(&matches) {
-        return Err(to_js_error("invalid matches payload", "expected array"));
+/// Indices of every hit in final order, ready to be paged. `scores`, `ids` and `hits` are
+/// parallel.
+fn rank_indices<I: HitId>(
+    scores: &[f64],
+    ids: &[I],
+    hits: &[HitFields],
+    config: &ScoreConfig,
+) -> Vec<usize> {
+    let mut ranked: Vec<usize> = (0..scores.len()).collect();
+    // Stable sort, so `TieBreak::OriginalRank` needs no extra key.
+    ranked.sort_by(|&a, &b| {
+        compare_scores_desc(scores[a], scores[b])
+            .then_with(|| tie_break_order(config, ids[a], ids[b]))
+    });
+
+    let Some(diversity) = &config.diversity else {
+        return ranked;
+    };
+    let items: Vec<DiversityItem> = ranked
+        .iter()
+        .map(|&index| DiversityItem {
+            score: scores[index],
+            company: hits[index].company.map(str::to_string),
+            city: hits[index].city.map(str::to_string),
+            state: hits[index].state.map(str::to_string),
+            website: hits[index].website.map(str::to_string),
+        })
+        .collect();
+    // Only the first page can be bounded up front; a cursor may point anywhere.
+    let mmr_limit = if config.cursor.is_some() {
+        items.len()
+    } else {
+        config.offset.saturating_add(config.limit)
+    };
+    diversity::mmr_rerank_impl(&items, diversity, mmr_limit)
+        .into_iter()
+        .map(|position| ranked[position])
+        .collect()
+}
+
+fn tie_break_order<I: HitId>(config: &ScoreConfig, a_id: I, b_id: I) -> Ordering {
+    match config.tie_break {
+        TieBreak::OriginalRank => Ordering::Equal,
+        TieBreak::Id => a_id.cmp(&b_id),
+        TieBreak::HashedId => a_id
+            .hashed(config.tie_break_seed)
+            .cmp(&b_id.hashed(config.tie_break_seed)),
     }
-    let matches_array: Array = matches
-        .clone()
-        .dyn_into()
-        .map_err(|_| to_js_error("invalid matches payload", "expected array"))?;
+}
 
-    if !profile.active || matches_array.length() == 0 {
-        return Ok(matches);
+/// Position in `ranked` of the first hit after `cursor`. When the cursor's hit is gone, this is
+/// the first hit that would sort after it. Under `TieBreak::OriginalRank` that skips every hit
+/// with the cursor's score, so pick `id` or `hashed_id` when results can change between pages.
+fn cursor_start<I: HitId>(
+    ranked: &[usize],
+    scores: &[f64],
+    ids: &[I],
+    (cursor_score, cursor_id): (f64, I),
+    config: &ScoreConfig,
+) -> usize {
+    let same_hit = |&index: &usize| {
+        ids[index] == cursor_id && scores[index].to_bits() == cursor_score.to_bits()
+    };
+    if let Some(position) = ranked.iter().position(same_hit) {
+        return position + 1;
     }
+    ranked
+        .iter()
+        .position(|&index| {
+            let tie = || match config.tie_break {
+                TieBreak::OriginalRank => Ordering::Less,
+                _ => tie_break_order(config, ids[index], cursor_id),
+            };
+            compare_scores_desc(scores[index], cursor_score).then_with(tie) == Ordering::Greater
+        })
+        .unwrap_or(ranked.len())
+}
+
+/// Range of `ranked` on the requested page: from `cursor` (the score and id of
+/// `config.cursor`) or `config.offset`, at most `config.limit` long.
+fn page_range<I: HitId>(
+    ranked: &[usize],
+    scores: &[f64],
+    ids: &[I],
+    cursor: Option<(f64, I)>,
+    config: &ScoreConfig,
+) -> Range<usize> {
+    let start = match cursor {
+        Some(cursor) => cursor_start(ranked, scores, ids, cursor, config),
+        None => config.offset.min(ranked.len()),
+    };
+    let end = start.saturating_add(config.limit).min(ranked.len());
+    start..end
+}
+
+/// Scores, ranks and returns one page of hits, starting at `config.cursor` or `config.offset`
+/// and holding at most `config.limit` hits.
+pub fn score_hits_page_impl(hits: Vec<HitInput>, config: &ScoreConfig) -> ScoredPage {
+    let fields: Vec<HitFields> = hits.iter().map(HitInput::fields).collect();
+    let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+    let (scores, mut explanations) = score_fields(&fields, config);
+    let ranked = rank_indices(&scores, &ids, &fields, config);
+    let cursor = config
+        .cursor
+        .as_ref()
+        .map(|cursor| (cursor.score, cursor.id.as_str()));
+    let page = page_range(&ranked, &scores, &ids, cursor, config);
+
+    let next_cursor = (page.end < ranked.len() && !page.is_empty()).then(|| {
+        let last = ranked[page.end - 1];
+        ScoreCursor {
+            score: scores[last],
+            id: ids[last].to_string(),
+        }
+    });
+
+    ScoredPage {
+        hits: ranked[page]
+            .iter()
+            .map(|&index| ScoredHit {
+                id: hits[index].id.clone(),
+                score: scores[index],
+                explanation: explanations[index].take(),
+            })
+            .collect(),
+        next_cursor,
+        total: ranked.len(),
+    }
+}
+
+/// Applies location boosts and penalties to vector-search hits and returns the top
+/// `config.limit` hits by descending score.
+pub fn score_hits_impl(hits: Vec<HitInput>, config: &ScoreConfig) -> Vec<ScoredHit> {
+    score_hits_page_impl(hits, config).hits
+}
+
+#[wasm_bindgen]
+pub fn score_hits(hits: JsValue, config: JsValue) -> Result<JsValue, ScorerError> {
+    let hits: Vec<HitInput> = from_js("hits", hits)?;
+    let config: ScoreConfig = from_js("config", config)?;
+
+    let scored_hits = score_hits_impl(hits, &config);
+    to_js("score output", &scored_hits)
+}
 
-    let terms = build_post_training_terms(&profile);
+/// `score_hits` returning `{ hits, nextCursor, total }` for cursor or offset pagination.
+#[wasm_bindgen]
+pub fn score_hits_page(hits: JsValue, config: JsValue) -> Result<JsValue, ScorerError> {
+    let hits: Vec<HitInput> = from_js("hits", hits)?;
+    let config: ScoreConfig = from_js("config", config)?;
 
-    let mut strictly_allowed: Vec<JsValue> =
-        Vec::with_capacity(matches_array.length() as usize);
-    let mut classified: Vec<ClassifiedMatch> =
-        Vec::with_capacity(matches_array.length() as usize);
+    let page = score_hits_page_impl(hits, &config);
+    to_js_with_nulls("score output", &page)
+}
 
-    for i in 0..matches_array.length() {
-        let js_match = matches_array.get(i);
-        let metadata = reflect_get(&js_match, "metadata").unwrap_or(JsValue::UNDEFINED);
+/// Filters and reorders matches according to a post-training profile. Returns indices into
+/// `matches` in output order, truncated to `final_limit` with JS `Array.slice` semantics.
+pub fn apply_post_training_impl(
+    matches: &[EsMatchInput],
+    profile: &PostTrainingProfile,
+    final_limit: f64,
+) -> Vec<usize> {
+    run_post_training(matches, profile, final_limit, false).indices
+}
 
-        let company_lc = metadata_value(&metadata, "company")
-            .unwrap_or_default()
-            .to_lowercase();
-        let title_lc = metadata_value(&metadata, "title")
-            .unwrap_or_default()
-            .to_lowercase();
-        let headline_lc = metadata_value(&metadata, "headline")
-            .unwrap_or_default()
-            .to_lowercase();
+/// Same ordering as [`apply_post_training_impl`], plus the tier and matched terms of every
+/// returned match, the exclude term behind every dropped one, and summary counts.
+pub fn apply_post_training_report_impl(
+    matches: &[EsMatchInput],
+    profile: &PostTrainingProfile,
+    final_limit: f64,
+) -> PostTrainingReport {
+    run_post_training(matches, profile, final_limit, true)
+}
+
+fn run_post_training(
+    matches: &[EsMatchInput],
+    profile: &PostTrainingProfile,
+    final_limit: f64,
+    report: bool,
+) -> PostTrainingReport {
+    let mut out = PostTrainingReport {
+        summary: PostTrainingSummary {
+            total: matches.len(),
+            ..PostTrainingSummary::default()
+        },
+        ..PostTrainingReport::default()
+    };
+
+    if !profile.active || matches.is_empty() {
+        out.indices = (0..matches.len()).collect();
+        if report {
+            out.classifications = out
+                .indices
+                .iter()
+                .map(|&index| PostTrainingClassification {
+                    index,
+                    tier: PostTrainingTier::Other,
+                    matched_terms: Vec::new(),
+                    post_training_score: None,
+                })
+                .collect();
+        }
+        out.summary.returned = out.indices.len();
+        return out;
+    }
 
-        let excluded = contains_any_lc(&company_lc, &terms.exclude_terms)
-            || contains_any_lc(&title_lc, &terms.exclude_terms)
-            || contains_any_lc(&headline_lc, &terms.exclude_terms);
-        if excluded {
+    let terms = build_post_training_terms(profile);
+    let classify = terms.require_positive || profile.scoring.is_some() || report;
+
+    let mut strictly_allowed: Vec<usize> = Vec::with_capacity(matches.len());
+    let mut classified: Vec<ClassifiedMatch> = Vec::with_capacity(matches.len());
+
+    for (index, es_match) in matches.iter().enumerate() {
+        let mut texts = MatchTexts {
+            company: normalize_text(es_match.company.as_deref().unwrap_or_default()),
+            title: normalize_text(es_match.title.as_deref().unwrap_or_default()),
+            headline: normalize_text(es_match.headline.as_deref().unwrap_or_default()),
+            website: normalize_text(es_match.website.as_deref().unwrap_or_default()),
+            industry: normalize_text(es_match.company_industry.as_deref().unwrap_or_default()),
+            ..MatchTexts::default()
+        };
+
+        if let Some((field, term)) = first_field_match(&terms.exclude_fields(&texts)) {
+            out.summary.excluded += 1;
+            if report {
+                out.exclusions.push(PostTrainingExclusion {
+                    index,
+                    term: term.term.clone(),
+                    field,
+                });
+            }
             continue;
         }
 
-        if !terms.require_positive {
-            strictly_allowed.push(js_match);
+        strictly_allowed.push(index);
+        if !classify {
             continue;
         }
 
-        let website_lc = metadata_value(&metadata, "website")
-            .unwrap_or_default()
-            .to_lowercase();
-        let industry_lc = metadata_value(&metadata, "companyIndustry")
-            .unwrap_or_default()
-            .to_lowercase();
-        let metadata_text_lc = metadata_value(&metadata, "metadata")
-            .unwrap_or_default()
-            .to_lowercase();
-
-        let positive = contains_any_lc(&company_lc, &terms.include_company_terms)
-            || contains_any_lc(&title_lc, &terms.include_title_terms)
-            || contains_any_lc(&headline_lc, &terms.include_company_or_title_terms)
-            || contains_any_lc(&website_lc, &terms.include_website_terms)
-            || contains_any_lc(&industry_lc, &terms.include_industry_terms)
-            || contains_any_lc(&metadata_text_lc, &terms.include_company_or_title_terms);
-
-        let aux = !positive
-            && (contains_any_lc(&company_lc, &terms.aux_company_terms)
-                || contains_any_lc(&title_lc, &terms.aux_title_terms)
-                || contains_any_lc(&headline_lc, &terms.aux_company_or_title_terms)
-                || contains_any_lc(&website_lc, &terms.aux_website_terms)
-                || contains_any_lc(&industry_lc, &terms.aux_industry_terms)
-                || contains_any_lc(&metadata_text_lc, &terms.aux_company_or_title_terms));
-
-        let demoted_positive = positive
-            && (contains_any_lc(&company_lc, &terms.demote_terms)
-                || contains_any_lc(&title_lc, &terms.demote_terms)
-                || contains_any_lc(&headline_lc, &terms.demote_terms));
-
-        let contact_id = metadata_value(&metadata, "contactId")
-            .filter(|value| !value.is_empty());
-        let id_fallback = match_id_or_empty(&js_match);
-        let key = contact_id.unwrap_or(id_fallback);
+        texts.metadata = normalize_text(es_match.metadata.as_deref().unwrap_or_default());
+
+        let positive = any_field_matches(&terms.include_fields(&texts));
+        let aux = !positive && any_field_matches(&terms.aux_fields(&texts));
+        let demoted_positive = positive && any_field_matches(&terms.demote_fields(&texts));
+        let post_training_score = profile
+            .scoring
+            .as_ref()
+            .map(|scoring| graded_post_training_score(&terms, scoring, &texts))
+            .unwrap_or_default();
+        let matched_terms = if report {
+            collect_term_hits(&terms, &texts)
+        } else {
+            Vec::new()
+        };
 
         classified.push(ClassifiedMatch {
-            js_match,
-            key,
+            index,
             positive,
             aux,
             demoted_positive,
+            post_training_score,
+            matched_terms,
         });
     }
 
-    if !terms.require_positive {
-        let end = slice_end_index(strictly_allowed.len(), final_limit);
-        let out = Array::new();
-        for js_match in strictly_allowed.iter().take(end) {
-            out.push(js_match);
+    let ranked = if let Some(scoring) = &profile.scoring {
+        rank_graded(matches, &classified, scoring)
+    } else if terms.require_positive {
+        rank_tiered(&classified)
+    } else {
+        strictly_allowed
+    };
+    let dedupe = profile.dedupe.clone().unwrap_or_default();
+    out.indices = dedupe_ranked(
+        matches,
+        ranked,
+        &dedupe,
+        terms.require_positive,
+        &mut out.summary,
+    );
+    let end = slice_end_index(out.indices.len(), final_limit);
+    if let Some(diversity) = &profile.diversity {
+        // Tiers carry no comparable score, so relevance falls with rank.
+        let total = out.indices.len();
+        let items: Vec<DiversityItem> = out
+            .indices
+            .iter()
+            .enumerate()
+            .map(|(position, &index)| DiversityItem {
+                score: (total - position) as f64,
+                company: matches[index].company.clone(),
+                city: matches[index].city.clone(),
+                state: matches[index].state.clone(),
+                website: matches[index].website.clone(),
+            })
+            .collect();
+        out.indices = diversity::mmr_rerank_impl(&items, diversity, end)
+            .into_iter()
+            .map(|position| out.indices[position])
+            .collect();
+    }
+    out.indices.truncate(end);
+    out.summary.returned = out.indices.len();
+
+    if report {
+        // `classified` is in input order, so each returned index can be found by binary search.
+        out.classifications = out
+            .indices
+            .iter()
+            .map(|&index| {
+                let position = classified
+                    .binary_search_by_key(&index, |entry| entry.index)
+                    .expect("returned index was classified");
+                let entry = &mut classified[position];
+                PostTrainingClassification {
+                    index,
+                    tier: entry.tier(),
+                    matched_terms: std::mem::take(&mut entry.matched_terms),
+                    post_training_score: profile
+                        .scoring
+                        .as_ref()
+                        .map(|_| entry.post_training_score),
+                }
+            })
+            .collect();
+    }
+
+    out
+}
+
+/// Orders classified matches tier by tier (positive, demoted positive, aux, everything else),
+/// keeping input order within each tier. A match is placed at most once.
+fn rank_tiered(classified: &[ClassifiedMatch]) -> Vec<usize> {
+    let tiers: [fn(&ClassifiedMatch) -> bool; 4] = [
+        |entry| entry.positive,
+        |entry| entry.demoted_positive,
+        |entry| !entry.positive && entry.aux,
+        |entry| !entry.positive && !entry.aux,
+    ];
+
+    let mut placed: HashSet<usize> = HashSet::new();
+    let mut ordered: Vec<usize> = Vec::with_capacity(classified.len());
+    for in_tier in tiers {
+        for entry in classified.iter().filter(|entry| in_tier(entry)) {
+            if placed.insert(entry.index) {
+                ordered.push(entry.index);
+            }
         }
-        return Ok(out.into());
     }
+    ordered
+}
+
+/// Orders classified matches by the blend of normalised ES score and post-training score.
+/// Ties keep their original order.
+fn rank_graded(
+    matches: &[EsMatchInput],
+    classified: &[ClassifiedMatch],
+    scoring: &PostTrainingScoring,
+) -> Vec<usize> {
+    let es_score = |index: usize| {
+        matches[index]
+            .score
+            .filter(|score| score.is_finite())
+            .unwrap_or(0.0)
+    };
+    let max_es_score = classified
+        .iter()
+        .map(|entry| es_score(entry.index))
+        .fold(0.0, f64::max);
+    let weight = scoring.weight.clamp(0.0, 1.0);
+
+    let mut ranked: Vec<(usize, f64)> = classified
+        .iter()
+        .map(|entry| {
+            let normalized_es = if max_es_score > 0.0 {
+                es_score(entry.index) / max_es_score
+            } else {
+                0.0
+            };
+            let blended = (1.0 - weight) * normalized_es + weight * entry.post_training_score;
+            (entry.index, blended)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.into_iter().map(|(index, _)| index).collect()
+}
+
+fn es_match_input(js_match: &JsValue, profile: &PostTrainingProfile, report: bool) -> EsMatchInput {
+    let metadata = reflect_get(js_match, "metadata").unwrap_or(JsValue::UNDEFINED);
+    let mut es_match = EsMatchInput {
+        id: match_id_or_empty(js_match),
+        contact_id: metadata_value(&metadata, "contactId"),
+        company: metadata_value(&metadata, "company"),
+        title: metadata_value(&metadata, "title"),
+        headline: metadata_value(&metadata, "headline"),
+        ..EsMatchInput::default()
+    };
+    let dedupe = profile.dedupe.clone().unwrap_or_default();
+    let dedupes_on = |key: DedupeKey| dedupe.keys.contains(&key);
+    let most_complete = dedupe.keep == DedupeKeep::MostComplete;
+    let diversifies_on = |key: DiversityKey| {
+        profile
+            .diversity
+            .as_ref()
+            .is_some_and(|diversity| diversity.uses(key))
+    };
+    let graded = profile.scoring.is_some();
+    let read_extended_fields = graded
+        || report
+        || profile.require_positive.unwrap_or(false)
+        || profile.strict_exclude.unwrap_or(false);
+    // Only some profile options look at these, so skip the Reflect calls otherwise.
+    if read_extended_fields {
+        es_match.metadata = metadata_value(&metadata, "metadata");
+    }
+    if read_extended_fields || most_complete {
+        es_match.company_industry = metadata_value(&metadata, "companyIndustry");
+    }
+    if read_extended_fields
+        || most_complete
+        || dedupes_on(DedupeKey::Domain)
+        || diversifies_on(DiversityKey::Domain)
+    {
+        es_match.website = metadata_value(&metadata, "website");
+    }
+    if most_complete || dedupes_on(DedupeKey::Email) {
+        es_match.email = metadata_value(&metadata, "email");
+    }
+    if most_complete || dedupes_on(DedupeKey::CompanyName) {
+        es_match.first_name = metadata_value(&metadata, "firstName");
+        es_match.last_name = metadata_value(&metadata, "lastName");
+    }
+    if diversifies_on(DiversityKey::City) {
+        es_match.city = metadata_value(&metadata, "city");
+    }
+    if diversifies_on(DiversityKey::State) {
+        es_match.state = metadata_value(&metadata, "state");
+    }
+    if graded || dedupe.keep == DedupeKeep::HighestScore {
+        es_match.score = reflect_get(js_match, "score").and_then(|score| score.as_f64());
+    }
+    es_match
+}
+
+fn post_training_args(
+    matches: JsValue,
+    profile: JsValue,
+    final_limit: JsValue,
+) -> Result<(Vec<JsValue>, PostTrainingProfile, f64), ScorerError> {
+    let profile: PostTrainingProfile = from_js("profile", profile)?;
+    let final_limit: f64 = from_js("finalLimit", final_limit)?;
+
+    if !Array::is_array(&matches) {
+        return Err(ScorerError::invalid_payload("matches", "expected array"));
+    }
+    let matches_array: Array = matches
+        .dyn_into()
+        .map_err(|_| ScorerError::invalid_payload("matches", "expected array"))?;
+
+    Ok((matches_array.iter().collect(), profile, final_limit))
+}
+
+fn es_match_inputs(
+    js_matches: &[JsValue],
+    profile: &PostTrainingProfile,
+    report: bool,
+) -> Vec<EsMatchInput> {
+    js_matches
+        .iter()
+        .map(|js_match| es_match_input(js_match, profile, report))
+        .collect()
+}
+
+fn pick_js_matches(js_matches: &[JsValue], indices: &[usize]) -> Array {
+    let out = Array::new();
+    for &index in indices {
+        out.push(&js_matches[index]);
+    }
+    out
+}
+
+#[wasm_bindgen]
+pub fn apply_post_training_to_es_matches(
+    matches: JsValue,
+    profile: JsValue,
+    final_limit: JsValue,
+) -> Result<JsValue, ScorerError> {
+    let (js_matches, profile, final_limit) =
+        post_training_args(matches.clone(), profile, final_limit)?;
+
+    if !profile.active || js_matches.is_empty() {
+        return Ok(matches);
+    }
+
+    let inputs = es_match_inputs(&js_matches, &profile, false);
+    let indices = apply_post_training_impl(&inputs, &profile, final_limit);
+    Ok(pick_js_matches(&js_matches, &indices).into())
+}
+
+/// Like `apply_post_training_to_es_matches`, but returns
+/// `{ matches, classifications, exclusions, summary }` where `classifications` is parallel to
+/// `matches`.
+#[wasm_bindgen]
+pub fn apply_post_training_to_es_matches_with_report(
+    matches: JsValue,
+    profile: JsValue,
+    final_limit: JsValue,
+) -> Result<JsValue, ScorerError> {
+    let (js_matches, profile, final_limit) = post_training_args(matches, profile, final_limit)?;
+
+    let inputs = es_match_inputs(&js_matches, &profile, true);
+    let report = apply_post_training_report_impl(&inputs, &profile, final_limit);
+
+    let result = js_sys::Object::new();
+    let fields: [(&str, JsValue); 4] = [
+        (
+            "matches",
+            pick_js_matches(&js_matches, &report.indices).into(),
+        ),
+        (
+            "classifications",
+            to_js("post-training report", &report.classifications)?,
+        ),
+        (
+            "exclusions",
+            to_js("post-training report", &report.exclusions)?,
+        ),
+        ("summary", to_js("post-training report", &report.summary)?),
+    ];
+    for (key, value) in fields {
+        Reflect::set(&result, &JsValue::from_str(key), &value).map_err(|_| {
+            ScorerError::serialization("post-training report", format!("could not set {key}"))
+        })?;
+    }
+    Ok(result.into())
+}
+
+/// Trimmed and lowercased only, like `filterItemsByTitlePrefixesTs`, so both paths keep the
+/// same items.
+fn title_key(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+fn item_title(item: &JsValue) -> Option<String> {
+    let direct = metadata_value(item, "title").filter(|title| !title.trim().is_empty());
+    if direct.is_some() {
+        return direct;
+    }
+    let metadata = reflect_get(item, "metadata").unwrap_or(JsValue::UNDEFINED);
+    metadata_value(&metadata, "title")
+}
+
+/// Returns indices of the titles that start with any of `prefixes`, both trimmed and
+/// lowercased. Missing or blank titles are kept only when `keep_null_titles` is set.
+pub fn filter_by_title_prefixes_impl(
+    titles: &[Option<&str>],
+    prefixes: &[String],
+    keep_null_titles: bool,
+) -> Vec<usize> {
+    let prefixes: Vec<String> = prefixes
+        .iter()
+        .map(|prefix| title_key(prefix))
+        .filter(|prefix| !prefix.is_empty())
+        .collect();
+    if prefixes.is_empty() {
+        return (0..titles.len()).collect();
+    }
+
+    titles
+        .iter()
+        .enumerate()
+        .filter(|(_, title)| {
+            let key = title_key(title.unwrap_or_default());
+            if key.is_empty() {
+                keep_null_titles
+            } else {
+                prefixes.iter().any(|prefix| key.starts_with(prefix))
+            }
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[wasm_bindgen]
+pub fn filter_items_by_title_prefixes(
+    items: JsValue,
+    prefixes: JsValue,
+    keep_null_titles: bool,
+) -> Result<JsValue, ScorerError> {
+    let prefixes: Vec<String> = from_js("prefixes", prefixes)?;
+
+    if !Array::is_array(&items) {
+        return Err(ScorerError::invalid_payload("items", "expected array"));
+    }
+    let items_array: Array = items
+        .dyn_into()
+        .map_err(|_| ScorerError::invalid_payload("items", "expected array"))?;
+
+    let js_items: Vec<JsValue> = items_array.iter().collect();
+    let titles: Vec<Option<String>> = js_items.iter().map(item_title).collect();
+    let title_refs: Vec<Option<&str>> = titles.iter().map(|title| title.as_deref()).collect();
+
+    let out = Array::new();
+    for index in filter_by_title_prefixes_impl(&title_refs, &prefixes, keep_null_titles) {
+        out.push(&js_items[index]);
+    }
+
+    Ok(out.into())
+}
 
-    let mut seen: HashSet<String> = HashSet::new();
-    let mut ordered: Vec<JsValue> = Vec::with_capacity(classified.len());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for entry in &classified {
-        if entry.positive {
-            push_classified_if_new(entry, &mut seen, &mut ordered);
+    fn es_match(id: &str, email: Option<&str>, website: Option<&str>) -> EsMatchInput {
+        EsMatchInput {
+            id: id.to_string(),
+            email: email.map(str::to_string),
+            website: website.map(str::to_string),
+            ..Default::default()
         }
-        if (ordered.len() as f64) >= final_limit {
-            break;
+    }
+
+    #[test]
+    fn cursor_round_trips_through_its_string_form() {
+        for score in [0.1 + 0.2, -0.0, f64::NAN, f64::MAX] {
+            let cursor = ScoreCursor {
+                score,
+                id: "hit:7".to_string(),
+            };
+            let parsed = ScoreCursor::try_from(String::from(cursor.clone())).unwrap();
+            assert_eq!(parsed.score.to_bits(), score.to_bits());
+            assert_eq!(parsed.id, cursor.id);
         }
+        assert!(ScoreCursor::try_from("not a cursor".to_string()).is_err());
     }
 
-    if (ordered.len() as f64) < final_limit {
-        for entry in &classified {
-            if entry.demoted_positive {
-                push_classified_if_new(entry, &mut seen, &mut ordered);
-            }
-            if (ordered.len() as f64) >= final_limit {
+    #[test]
+    fn cursor_pages_cover_every_hit_once() {
+        let hits: Vec<HitInput> = [2.0, 1.0, 2.0, f64::NAN, 1.0, 3.0, 2.0]
+            .iter()
+            .enumerate()
+            .map(|(index, &score)| HitInput {
+                id: format!("hit-{index}"),
+                score,
+                ..Default::default()
+            })
+            .collect();
+        let mut config = ScoreConfig {
+            limit: 2,
+            tie_break: TieBreak::Id,
+            ..Default::default()
+        };
+        let everything = ScoreConfig {
+            limit: hits.len(),
+            ..config.clone()
+        };
+        let all: Vec<String> = score_hits_page_impl(hits.clone(), &everything)
+            .hits
+            .into_iter()
+            .map(|hit| hit.id)
+            .collect();
+
+        let mut paged: Vec<String> = Vec::new();
+        loop {
+            let page = score_hits_page_impl(hits.clone(), &config);
+            assert_eq!(page.total, hits.len());
+            paged.extend(page.hits.into_iter().map(|hit| hit.id));
+            let Some(next) = page.next_cursor else {
                 break;
-            }
+            };
+            config.cursor = Some(next);
         }
+        assert_eq!(paged, all);
     }
 
-    if (ordered.len() as f64) < final_limit {
-        for entry in &classified {
-            if !entry.positive && entry.aux {
-                push_classified_if_new(entry, &mut seen, &mut ordered);
-            }
-            if (ordered.len() as f64) >= final_limit {
-                break;
-            }
+    #[test]
+    fn dedupe_merges_groups_bridged_by_a_later_match() {
+        let matches = [
+            es_match("a", Some("e1@example.com"), None),
+            es_match("c", None, Some("https://d1.example")),
+            es_match("b", Some("e1@example.com"), Some("https://www.d1.example/")),
+        ];
+        let dedupe = PostTrainingDedupe {
+            keys: vec![DedupeKey::Email, DedupeKey::Domain],
+            keep: DedupeKeep::First,
+        };
+        let mut summary = PostTrainingSummary::default();
+        let kept = dedupe_ranked(&matches, vec![0, 1, 2], &dedupe, false, &mut summary);
+        assert_eq!(kept, vec![0]);
+        assert_eq!(summary.deduplicated, 2);
+    }
+
+    #[test]
+    fn dedupe_merged_group_keeps_the_preferred_winner() {
+        let mut matches = [
+            es_match("a", Some("e1@example.com"), None),
+            es_match("c", None, Some("https://d1.example")),
+            es_match("b", Some("e1@example.com"), Some("https://d1.example")),
+        ];
+        matches[1].score = Some(9.0);
+        let dedupe = PostTrainingDedupe {
+            keys: vec![DedupeKey::Email, DedupeKey::Domain],
+            keep: DedupeKeep::HighestScore,
+        };
+        let mut summary = PostTrainingSummary::default();
+        let kept = dedupe_ranked(&matches, vec![0, 1, 2], &dedupe, false, &mut summary);
+        assert_eq!(kept, vec![1]);
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn titled_match(id: &str, company: &str, title: &str) -> EsMatchInput {
+        EsMatchInput {
+            id: id.to_string(),
+            company: Some(company.to_string()),
+            title: Some(title.to_string()),
+            ..Default::default()
         }
     }
 
-    if (ordered.len() as f64) < final_limit {
-        for entry in &classified {
-            if !entry.positive && !entry.aux {
-                push_classified_if_new(entry, &mut seen, &mut ordered);
-            }
-            if (ordered.len() as f64) >= final_limit {
-                break;
-            }
+    #[test]
+    fn exclude_terms_match_whole_words_by_default() {
+        let matches = [
+            titled_match("1", "Barbara Events", "Planner"),
+            titled_match("2", "Campus", "Adjunct Professor"),
+            titled_match("3", "The Blue Bar", "Manager"),
+            titled_match("4", "Nightlife Co", "Resident DJ"),
+        ];
+        let mut profile = PostTrainingProfile {
+            active: true,
+            exclude_terms: strings(&["bar", "dj"]),
+            ..Default::default()
+        };
+        let kept = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(kept, vec![0, 1]);
+
+        profile.term_match = TermMode::Substring;
+        assert!(apply_post_training_impl(&matches, &profile, 10.0).is_empty());
+    }
+
+    #[test]
+    fn punctuated_exclude_terms_do_not_run_into_the_next_word() {
+        // The shipped `wedding_planner` profile.
+        let mut profile = PostTrainingProfile {
+            active: true,
+            exclude_terms: strings(&["weddingwire", "theknot", "wedding.com"]),
+            strict_exclude: Some(true),
+            require_positive: Some(false),
+            include_company_terms: strings(&["wedding", "bridal"]),
+            include_title_terms: strings(&["planner", "coordinator", "consultant"]),
+            include_industry_terms: strings(&["wedding", "events"]),
+            ..Default::default()
+        };
+        let mut listed = titled_match("2", "Bella Bridal", "Planner");
+        listed.website = Some("https://www.wedding.com/vendors/bella".to_string());
+        let matches = [
+            titled_match("1", "Bella Wedding Company", "Planner"),
+            listed,
+            titled_match("3", "Wedding.com Partners", "Coordinator"),
+        ];
+        for term_match in [TermMode::Word, TermMode::Substring] {
+            profile.term_match = term_match;
+            let kept = apply_post_training_impl(&matches, &profile, 10.0);
+            assert_eq!(kept, vec![0], "{term_match:?}");
         }
     }
 
-    let end = slice_end_index(ordered.len(), final_limit);
-    let out = Array::new();
-    for js_match in ordered.iter().take(end) {
-        out.push(js_match);
+    #[test]
+    fn pages_and_total_respect_diversity_caps() {
+        let companies = ["acme", "acme", "globex", "acme", "initech", "acme"];
+        let hits: Vec<HitInput> = companies
+            .iter()
+            .enumerate()
+            .map(|(index, company)| HitInput {
+                id: format!("hit-{index}"),
+                score: 10.0 - index as f64,
+                company: Some(company.to_string()),
+                ..Default::default()
+            })
+            .collect();
+        let mut config = ScoreConfig {
+            limit: 2,
+            diversity: Some(DiversityConfig {
+                caps: HashMap::from([(DiversityKey::Company, 1)]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let first = score_hits_page_impl(hits.clone(), &config);
+        let ids: Vec<&str> = first.hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!((ids, first.total), (vec!["hit-0", "hit-2"], 3));
+
+        config.cursor = first.next_cursor;
+        let second = score_hits_page_impl(hits, &config);
+        let ids: Vec<&str> = second.hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!((ids, second.total), (vec!["hit-4"], 3));
+        assert!(second.next_cursor.is_none());
     }
 
-    Ok(out.into())
-}
+    #[test]
+    fn explanation_breaks_the_score_into_boosts_and_penalties() {
+        let hit = HitInput {
+            id: "hit-0".to_string(),
+            score: 1.0,
+            city: Some("Austin".to_string()),
+            state: Some("Texas".to_string()),
+            country: Some("United States".to_string()),
+            headline: Some("Senior Recruiter".to_string()),
+            ..Default::default()
+        };
+        let config = ScoreConfig {
+            query_city: Some("austin".to_string()),
+            query_state: Some("TX".to_string()),
+            query_country: Some("United States".to_string()),
+            exact_boost: 0.5,
+            fuzzy_boost: 0.25,
+            penalty_cities: strings(&["Austin"]),
+            penalty_terms: strings(&["recruiter", "talent"]),
+            explain: true,
+            ..Default::default()
+        };
+
+        let scored = score_hits_impl(vec![hit.clone()], &config);
+        let explanation = scored[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.base_score, 1.0);
+        assert_eq!(explanation.exact_state_boost, 0.0);
+        assert_eq!(explanation.alias_state_boost, 0.5);
+        assert_eq!(explanation.fuzzy_state_boost, 0.25);
+        assert_eq!(explanation.exact_city_boost, 0.5);
+        assert_eq!(explanation.exact_country_boost, 0.5);
+        assert_eq!(explanation.location_boost, 1.75);
+        assert_eq!(explanation.penalty_city.as_deref(), Some("austin"));
+        assert_eq!(explanation.penalty_city_amount, 0.2);
+        assert_eq!(explanation.penalty_terms.len(), 1);
+        let term = &explanation.penalty_terms[0];
+        assert_eq!(term.term, "recruiter");
+        assert_eq!(term.field, PenaltyField::TextBlob);
+        assert!(term.exact);
+        assert_eq!(term.penalty, 0.35);
+        assert_eq!(explanation.penalty, 0.2 + 0.35);
+        assert!(!explanation.penalty_capped);
+        let expected = explanation.base_score + explanation.location_boost - explanation.penalty;
+        assert_eq!(scored[0].score, expected);
+
+        let quiet = ScoreConfig {
+            explain: false,
+            ..config
+        };
+        let scored_quietly = score_hits_impl(vec![hit], &quiet);
+        assert!(scored_quietly[0].explanation.is_none());
+        assert_eq!(scored_quietly[0].score, expected);
+    }
 
+    #[test]
+    fn penalty_weights_default_to_the_old_constants() {
+        let hit = HitInput {
+            id: "hit-0".to_string(),
+            score: 1.0,
+            headline: Some("Staffing agency".to_string()),
+            ..Default::default()
+        };
+        let penalty = |config: ScoreConfig| {
+            let config = ScoreConfig {
+                penalty_terms: strings(&["agency", "staff"]),
+                explain: true,
+                ..config
+            };
+            let scored = score_hits_impl(vec![hit.clone()], &config);
+            scored[0].explanation.as_ref().unwrap().penalty
+        };
+        assert_eq!(penalty(ScoreConfig::default()), 0.35 + 0.2);
+        let strict = ScoreConfig {
+            strict_penalty: true,
+            ..Default::default()
+        };
+        assert_eq!(penalty(strict), 0.6 + 0.35);
+    }
+
+    #[test]
+    fn penalty_weights_overrides_and_cap() {
+        let hit = HitInput {
+            id: "hit-0".to_string(),
+            score: 2.0,
+            city: Some("Reno".to_string()),
+            headline: Some("Staffing agency".to_string()),
+            ..Default::default()
+        };
+        let mut config = ScoreConfig {
+            penalty_cities: strings(&["reno"]),
+            penalty_terms: strings(&["agency", "staff", "Staffing"]),
+            penalty_city_weight: Some(0.5),
+            penalty_term_exact_weight: Some(0.25),
+            penalty_term_substring_weight: Some(0.125),
+            penalty_term_weights: HashMap::from([(
+                "AGENCY".to_string(),
+                PenaltyTermWeight {
+                    exact: Some(1.0),
+                    substring: None,
+                },
+            )]),
+            explain: true,
+            ..Default::default()
+        };
+
+        let scored = score_hits_impl(vec![hit.clone()], &config);
+        let explanation = scored[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.penalty_city_amount, 0.5);
+        let amounts: Vec<(&str, bool, f64)> = explanation
+            .penalty_terms
+            .iter()
+            .map(|term| (term.term.as_str(), term.exact, term.penalty))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                ("agency", true, 1.0),
+                ("staff", false, 0.125),
+                ("staffing", true, 0.25)
+            ]
+        );
+        assert_eq!(explanation.penalty, 0.5 + 1.0 + 0.125 + 0.25);
+        assert!(!explanation.penalty_capped);
+
+        config.max_penalty = Some(1.5);
+        let scored = score_hits_impl(vec![hit], &config);
+        let explanation = scored[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.penalty, 1.5);
+        assert!(explanation.penalty_capped);
+        assert_eq!(scored[0].score, 0.5);
+    }
+
+    #[test]
+    fn penalty_rules_only_check_their_fields() {
+        let hits = vec![
+            HitInput {
+                id: "headline-agency".to_string(),
+                score: 1.0,
+                headline: Some("Ex-agency booker".to_string()),
+                company: Some("Blue Note".to_string()),
+                ..Default::default()
+            },
+            HitInput {
+                id: "company-agency".to_string(),
+                score: 1.0,
+                company: Some("Bright Agency".to_string()),
+                ..Default::default()
+            },
+        ];
+        let config = ScoreConfig {
+            penalty_rules: vec![PenaltyRule {
+                term: "agency".to_string(),
+                fields: vec![PenaltyField::Company],
+                weight: Some(0.5),
+                substring_weight: None,
+            }],
+            explain: true,
+            ..Default::default()
+        };
+
+        let scored = score_hits_impl(hits, &config);
+        assert_eq!(scored[0].id, "headline-agency");
+        assert_eq!(scored[0].score, 1.0);
+        let explanation = scored[1].explanation.as_ref().unwrap();
+        assert_eq!(explanation.penalty_terms.len(), 1);
+        assert_eq!(explanation.penalty_terms[0].field, PenaltyField::Company);
+        assert_eq!(explanation.penalty, 0.5);
+    }
+
+    #[test]
+    fn text_blob_terms_still_match_across_fields() {
+        let hit = HitInput {
+            id: "hit-0".to_string(),
+            score: 1.0,
+            headline: Some("Talent booking".to_string()),
+            title: Some("Agent for touring bands".to_string()),
+            ..Default::default()
+        };
+        let rule = |fields: Vec<PenaltyField>| PenaltyRule {
+            term: "booking agent".to_string(),
+            fields,
+            weight: Some(0.5),
+            substring_weight: None,
+        };
+        let penalty = |penalty_rules: Vec<PenaltyRule>, penalty_terms: Vec<String>| {
+            let config = ScoreConfig {
+                penalty_rules,
+                penalty_terms,
+                explain: true,
+                ..Default::default()
+            };
+            let scored = score_hits_impl(vec![hit.clone()], &config);
+            scored[0].explanation.as_ref().unwrap().penalty
+        };
+
+        assert_eq!(penalty(Vec::new(), strings(&["booking agent"])), 0.35);
+        assert_eq!(penalty(vec![rule(Vec::new())], Vec::new()), 0.5);
+        let per_field = rule(vec![PenaltyField::Headline, PenaltyField::Title]);
+        assert_eq!(penalty(vec![per_field], Vec::new()), 0.0);
+    }
+
+    #[test]
+    fn distance_curves_decay_with_distance() {
+        assert_eq!(DistanceCurve::Linear.weight(0.0, 50.0), 1.0);
+        assert_eq!(DistanceCurve::Linear.weight(25.0, 50.0), 0.5);
+        assert_eq!(DistanceCurve::Linear.weight(80.0, 50.0), 0.0);
+        assert_eq!(DistanceCurve::Gaussian.weight(0.0, 50.0), 1.0);
+        assert_eq!(DistanceCurve::Gaussian.weight(50.0, 50.0), (-0.5f64).exp());
+        assert_eq!(DistanceCurve::Step.weight(50.0, 50.0), 1.0);
+        assert_eq!(DistanceCurve::Step.weight(50.1, 50.0), 0.0);
+        for curve in [
+            DistanceCurve::Linear,
+            DistanceCurve::Gaussian,
+            DistanceCurve::Step,
+        ] {
+            assert_eq!(curve.weight(f64::NAN, 50.0), 0.0);
+            assert_eq!(curve.weight(10.0, 0.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn distance_boost_favours_nearby_hits() {
+        let at = |id: &str, lat: Option<f64>, lng: Option<f64>| HitInput {
+            id: id.to_string(),
+            score: 1.0,
+            lat,
+            lng,
+            ..Default::default()
+        };
+        // Roughly 11 km and 56 km north of the query point.
+        let hits = vec![
+            at("far", Some(40.5), Some(-74.0)),
+            at("unknown", None, None),
+            at("near", Some(40.1), Some(-74.0)),
+        ];
+        let config = ScoreConfig {
+            query_lat: Some(40.0),
+            query_lng: Some(-74.0),
+            distance_boost: 1.0,
+            explain: true,
+            ..Default::default()
+        };
+
+        let scored = score_hits_impl(hits.clone(), &config);
+        let order: Vec<&str> = scored.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!(order, vec!["near", "far", "unknown"]);
+        let near = scored[0].explanation.as_ref().unwrap();
+        let distance_km = near.distance_km.unwrap();
+        assert!((distance_km - 11.1).abs() < 0.1, "{distance_km}");
+        assert_eq!(near.distance_boost, 1.0 - distance_km / 50.0);
+        assert_eq!(scored[1].score, 1.0);
+        let unknown = scored[2].explanation.as_ref().unwrap();
+        assert!(unknown.distance_km.is_none());
+
+        let step = ScoreConfig {
+            distance_curve: DistanceCurve::Step,
+            distance_scale_km: Some(100.0),
+            ..config
+        };
+        let scores: Vec<f64> = score_hits_impl(hits, &step)
+            .iter()
+            .map(|hit| hit.score)
+            .collect();
+        assert_eq!(scores, vec![2.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn strict_exclude_also_checks_website_and_industry() {
+        let mut by_industry = titled_match("2", "Northside Events", "Booker");
+        by_industry.company_industry = Some("Talent Agency".to_string());
+        let mut by_website = titled_match("3", "Northside Live", "Booker");
+        by_website.website = Some("https://agency.example.com".to_string());
+        let matches = [
+            titled_match("1", "Bright Agency", "Booker"),
+            by_industry,
+            by_website,
+            titled_match("4", "Blue Note", "Booker"),
+        ];
+        let mut profile = PostTrainingProfile {
+            active: true,
+            exclude_terms: strings(&["agency"]),
+            ..Default::default()
+        };
+        let lenient = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(lenient, vec![1, 2, 3]);
+
+        profile.strict_exclude = Some(true);
+        let strict = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(strict, vec![3]);
+    }
+
+    #[test]
+    fn unknown_profile_fields_are_rejected() {
+        use serde::de::value::{Error, MapDeserializer};
+
+        let parse = |fields: Vec<(&'static str, bool)>| {
+            PostTrainingProfile::deserialize(MapDeserializer::<_, Error>::new(fields.into_iter()))
+        };
+        assert!(parse(vec![("active", true)]).unwrap().active);
+        let error = parse(vec![("active", true), ("strictExclusion", true)]).unwrap_err();
+        assert!(error.to_string().contains("strictExclusion"), "{error}");
+    }
+
+    #[test]
+    fn graded_scoring_lets_a_strong_aux_match_outrank_a_weak_positive() {
+        let mut other = titled_match("1", "Northside Live", "Booker");
+        other.score = Some(4.0);
+        let mut weak_positive = titled_match("2", "Freelance", "Assistant");
+        weak_positive.headline = Some("Part-time planner".to_string());
+        weak_positive.score = Some(1.0);
+        let mut strong_aux = titled_match("3", "Event Venue Hall", "Venue Manager");
+        strong_aux.score = Some(10.0);
+        let matches = [other, weak_positive, strong_aux];
+        let mut profile = PostTrainingProfile {
+            active: true,
+            require_positive: Some(true),
+            include_title_terms: strings(&["planner"]),
+            aux_company_terms: strings(&["venue", "hall"]),
+            aux_title_terms: strings(&["manager"]),
+            ..Default::default()
+        };
+        let tiered = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(tiered, vec![1, 2, 0]);
+
+        profile.scoring = Some(PostTrainingScoring::default());
+        let graded = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(graded, vec![2, 1, 0]);
+
+        profile.scoring = Some(PostTrainingScoring {
+            weight: 0.0,
+            ..Default::default()
+        });
+        let es_only = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(es_only, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn graded_score_rewards_include_hits_and_subtracts_demotes() {
+        let profile = PostTrainingProfile {
+            active: true,
+            demote_terms: strings(&["assistant"]),
+            include_title_terms: strings(&["planner"]),
+            scoring: Some(PostTrainingScoring::default()),
+            ..Default::default()
+        };
+        let matches = [
+            titled_match("1", "Bella Events", "Planner"),
+            titled_match("2", "Bella Events", "Assistant Planner"),
+            titled_match("3", "Bella Events", "Assistant"),
+        ];
+        let report = apply_post_training_report_impl(&matches, &profile, 10.0);
+        assert_eq!(report.indices, vec![0, 1, 2]);
+        let scores: Vec<f64> = report
+            .classifications
+            .iter()
+            .map(|entry| entry.post_training_score.unwrap())
+            .collect();
+        // Include: 1.0 per term in the title field. Demote: 0.5. Squashed by x / (1 + |x|).
+        assert_eq!(scores, vec![0.5, 0.5 / 1.5, -0.5 / 1.5]);
+    }
+
+    #[test]
+    fn report_lists_tiers_terms_exclusions_and_counts() {
+        let mut first = titled_match("1", "Bella Events", "Wedding Planner");
+        first.contact_id = Some("c-1".to_string());
+        let mut duplicate = titled_match("4", "Bella Events", "Wedding Planner");
+        duplicate.contact_id = Some("c-1".to_string());
+        let matches = [
+            first,
+            titled_match("2", "Bright Agency", "Planner"),
+            titled_match("3", "Grand Hall", "Venue Manager"),
+            duplicate,
+            titled_match("5", "Freelance", "Assistant Planner"),
+        ];
+        let profile = PostTrainingProfile {
+            active: true,
+            exclude_terms: strings(&["agency"]),
+            demote_terms: strings(&["assistant"]),
+            include_title_terms: strings(&["planner"]),
+            aux_company_terms: strings(&["hall"]),
+            ..Default::default()
+        };
+
+        let report = apply_post_training_report_impl(&matches, &profile, 10.0);
+        assert_eq!(report.indices, vec![0, 2, 4]);
+        let indices = apply_post_training_impl(&matches, &profile, 10.0);
+        assert_eq!(report.indices, indices);
+
+        let tiers: Vec<(usize, PostTrainingTier)> = report
+            .classifications
+            .iter()
+            .map(|entry| (entry.index, entry.tier))
+            .collect();
+        assert_eq!(
+            tiers,
+            vec![
+                (0, PostTrainingTier::Positive),
+                (2, PostTrainingTier::Aux),
+                (4, PostTrainingTier::DemotedPositive),
+            ]
+        );
+        let terms = &report.classifications[0].matched_terms;
+        assert!(terms.iter().any(|hit| hit.term == "planner"
+            && hit.field == PostTrainingField::Title
+            && hit.kind == PostTrainingTermKind::Include));
+        assert!(report.classifications[0].post_training_score.is_none());
+        let demoted = &report.classifications[2].matched_terms;
+        assert!(demoted
+            .iter()
+            .any(|hit| hit.term == "assistant" && hit.kind == PostTrainingTermKind::Demote));
+
+        assert_eq!(report.exclusions.len(), 1);
+        let exclusion = &report.exclusions[0];
+        assert_eq!((exclusion.index, exclusion.term.as_str()), (1, "agency"));
+        assert_eq!(exclusion.field, PostTrainingField::Company);
+
+        let summary = &report.summary;
+        assert_eq!((summary.total, summary.excluded), (5, 1));
+        assert_eq!((summary.deduplicated, summary.missing_key), (1, 0));
+        assert_eq!(summary.returned, 3);
+    }
+}
@@ -0,0 +1,305 @@
+//! State/province normalisation for location matching.
+//!
+//! Maps US state names, USPS codes, Canadian provinces and territories, informal
+//! abbreviations and common misspellings to ISO 3166-2 style IDs (`US-CA`, `CA-ON`).
+
+use crate::text::normalize_text;
+
+pub struct Region {
+    /// ISO 3166-2 style ID, e.g. `US-NY` or `CA-QC`.
+    pub id: &'static str,
+    pub name: &'static str,
+    /// USPS or Canada Post code.
+    pub code: &'static str,
+}
+
+const fn region(id: &'static str, name: &'static str, code: &'static str) -> Region {
+    Region { id, name, code }
+}
+
+pub const REGIONS: &[Region] = &[
+    region("US-AL", "Alabama", "AL"),
+    region("US-AK", "Alaska", "AK"),
+    region("US-AZ", "Arizona", "AZ"),
+    region("US-AR", "Arkansas", "AR"),
+    region("US-CA", "California", "CA"),
+    region("US-CO", "Colorado", "CO"),
+    region("US-CT", "Connecticut", "CT"),
+    region("US-DE", "Delaware", "DE"),
+    region("US-DC", "District of Columbia", "DC"),
+    region("US-FL", "Florida", "FL"),
+    region("US-GA", "Georgia", "GA"),
+    region("US-HI", "Hawaii", "HI"),
+    region("US-ID", "Idaho", "ID"),
+    region("US-IL", "Illinois", "IL"),
+    region("US-IN", "Indiana", "IN"),
+    region("US-IA", "Iowa", "IA"),
+    region("US-KS", "Kansas", "KS"),
+    region("US-KY", "Kentucky", "KY"),
+    region("US-LA", "Louisiana", "LA"),
+    region("US-ME", "Maine", "ME"),
+    region("US-MD", "Maryland", "MD"),
+    region("US-MA", "Massachusetts", "MA"),
+    region("US-MI", "Michigan", "MI"),
+    region("US-MN", "Minnesota", "MN"),
+    region("US-MS", "Mississippi", "MS"),
+    region("US-MO", "Missouri", "MO"),
+    region("US-MT", "Montana", "MT"),
+    region("US-NE", "Nebraska", "NE"),
+    region("US-NV", "Nevada", "NV"),
+    region("US-NH", "New Hampshire", "NH"),
+    region("US-NJ", "New Jersey", "NJ"),
+    region("US-NM", "New Mexico", "NM"),
+    region("US-NY", "New York", "NY"),
+    region("US-NC", "North Carolina", "NC"),
+    region("US-ND", "North Dakota", "ND"),
+    region("US-OH", "Ohio", "OH"),
+    region("US-OK", "Oklahoma", "OK"),
+    region("US-OR", "Oregon", "OR"),
+    region("US-PA", "Pennsylvania", "PA"),
+    region("US-RI", "Rhode Island", "RI"),
+    region("US-SC", "South Carolina", "SC"),
+    region("US-SD", "South Dakota", "SD"),
+    region("US-TN", "Tennessee", "TN"),
+    region("US-TX", "Texas", "TX"),
+    region("US-UT", "Utah", "UT"),
+    region("US-VT", "Vermont", "VT"),
+    region("US-VA", "Virginia", "VA"),
+    region("US-WA", "Washington", "WA"),
+    region("US-WV", "West Virginia", "WV"),
+    region("US-WI", "Wisconsin", "WI"),
+    region("US-WY", "Wyoming", "WY"),
+    region("CA-AB", "Alberta", "AB"),
+    region("CA-BC", "British Columbia", "BC"),
+    region("CA-MB", "Manitoba", "MB"),
+    region("CA-NB", "New Brunswick", "NB"),
+    region("CA-NL", "Newfoundland and Labrador", "NL"),
+    region("CA-NS", "Nova Scotia", "NS"),
+    region("CA-NT", "Northwest Territories", "NT"),
+    region("CA-NU", "Nunavut", "NU"),
+    region("CA-ON", "Ontario", "ON"),
+    region("CA-PE", "Prince Edward Island", "PE"),
+    region("CA-QC", "Quebec", "QC"),
+    region("CA-SK", "Saskatchewan", "SK"),
+    region("CA-YT", "Yukon", "YT"),
+];
+
+// Informal names and misspellings, keyed by their normalised form (see `region_key`).
+const REGION_ALIASES: &[(&str, &str)] = &[
+    ("ala", "US-AL"),
+    ("ariz", "US-AZ"),
+    ("arizonia", "US-AZ"),
+    ("ark", "US-AR"),
+    ("cal", "US-CA"),
+    ("calif", "US-CA"),
+    ("cali", "US-CA"),
+    ("califronia", "US-CA"),
+    ("californa", "US-CA"),
+    ("colo", "US-CO"),
+    ("conn", "US-CT"),
+    ("conneticut", "US-CT"),
+    ("connecticutt", "US-CT"),
+    ("del", "US-DE"),
+    ("d c", "US-DC"),
+    ("washington dc", "US-DC"),
+    ("washington d c", "US-DC"),
+    ("fla", "US-FL"),
+    ("flordia", "US-FL"),
+    ("ill", "US-IL"),
+    ("ilinois", "US-IL"),
+    ("illnois", "US-IL"),
+    ("ind", "US-IN"),
+    ("kan", "US-KS"),
+    ("kans", "US-KS"),
+    ("kentucy", "US-KY"),
+    ("louisianna", "US-LA"),
+    ("mass", "US-MA"),
+    ("massachusets", "US-MA"),
+    ("massachussetts", "US-MA"),
+    ("massachusettes", "US-MA"),
+    ("mich", "US-MI"),
+    ("minn", "US-MN"),
+    ("minnesotta", "US-MN"),
+    ("miss", "US-MS"),
+    ("missisippi", "US-MS"),
+    ("mississipi", "US-MS"),
+    ("missourri", "US-MO"),
+    ("mont", "US-MT"),
+    ("neb", "US-NE"),
+    ("nebr", "US-NE"),
+    ("nev", "US-NV"),
+    ("n carolina", "US-NC"),
+    ("n dakota", "US-ND"),
+    ("new york state", "US-NY"),
+    ("ny state", "US-NY"),
+    ("okla", "US-OK"),
+    ("ore", "US-OR"),
+    ("penn", "US-PA"),
+    ("penna", "US-PA"),
+    ("pensylvania", "US-PA"),
+    ("pennsilvania", "US-PA"),
+    ("pennslyvania", "US-PA"),
+    ("s carolina", "US-SC"),
+    ("s dakota", "US-SD"),
+    ("tenn", "US-TN"),
+    ("tennesee", "US-TN"),
+    ("tenessee", "US-TN"),
+    ("tex", "US-TX"),
+    ("wash", "US-WA"),
+    ("washington state", "US-WA"),
+    ("wis", "US-WI"),
+    ("wisc", "US-WI"),
+    ("wisconson", "US-WI"),
+    ("w virginia", "US-WV"),
+    ("wyo", "US-WY"),
+    ("alta", "CA-AB"),
+    ("b c", "CA-BC"),
+    ("man", "CA-MB"),
+    ("newfoundland", "CA-NL"),
+    ("labrador", "CA-NL"),
+    ("nfld", "CA-NL"),
+    ("pei", "CA-PE"),
+    ("p e i", "CA-PE"),
+    ("que", "CA-QC"),
+    ("pq", "CA-QC"),
+    ("sask", "CA-SK"),
+    ("yukon territory", "CA-YT"),
+    ("nwt", "CA-NT"),
+    ("ont", "CA-ON"),
+];
+
+// Land borders between regions, each pair listed once.
+#[rustfmt::skip]
+const ADJACENT_REGIONS: &[(&str, &str)] = &[
+    ("US-AL", "US-FL"), ("US-AL", "US-GA"), ("US-AL", "US-MS"), ("US-AL", "US-TN"),
+    ("US-AZ", "US-CA"), ("US-AZ", "US-CO"), ("US-AZ", "US-NM"), ("US-AZ", "US-NV"),
+    ("US-AZ", "US-UT"), ("US-AR", "US-LA"), ("US-AR", "US-MO"), ("US-AR", "US-MS"),
+    ("US-AR", "US-OK"), ("US-AR", "US-TN"), ("US-AR", "US-TX"), ("US-CA", "US-NV"),
+    ("US-CA", "US-OR"), ("US-CO", "US-KS"), ("US-CO", "US-NE"), ("US-CO", "US-NM"),
+    ("US-CO", "US-OK"), ("US-CO", "US-UT"), ("US-CO", "US-WY"), ("US-CT", "US-MA"),
+    ("US-CT", "US-NY"), ("US-CT", "US-RI"), ("US-DE", "US-MD"), ("US-DE", "US-NJ"),
+    ("US-DE", "US-PA"), ("US-DC", "US-MD"), ("US-DC", "US-VA"), ("US-FL", "US-GA"),
+    ("US-GA", "US-NC"), ("US-GA", "US-SC"), ("US-GA", "US-TN"), ("US-ID", "US-MT"),
+    ("US-ID", "US-NV"), ("US-ID", "US-OR"), ("US-ID", "US-UT"), ("US-ID", "US-WA"),
+    ("US-ID", "US-WY"), ("US-IL", "US-IA"), ("US-IL", "US-IN"), ("US-IL", "US-KY"),
+    ("US-IL", "US-MO"), ("US-IL", "US-WI"), ("US-IN", "US-KY"), ("US-IN", "US-MI"),
+    ("US-IN", "US-OH"), ("US-IA", "US-MN"), ("US-IA", "US-MO"), ("US-IA", "US-NE"),
+    ("US-IA", "US-SD"), ("US-IA", "US-WI"), ("US-KS", "US-MO"), ("US-KS", "US-NE"),
+    ("US-KS", "US-OK"), ("US-KY", "US-MO"), ("US-KY", "US-OH"), ("US-KY", "US-TN"),
+    ("US-KY", "US-VA"), ("US-KY", "US-WV"), ("US-LA", "US-MS"), ("US-LA", "US-TX"),
+    ("US-ME", "US-NH"), ("US-MD", "US-PA"), ("US-MD", "US-VA"), ("US-MD", "US-WV"),
+    ("US-MA", "US-NH"), ("US-MA", "US-NY"), ("US-MA", "US-RI"), ("US-MA", "US-VT"),
+    ("US-MI", "US-OH"), ("US-MI", "US-WI"), ("US-MN", "US-ND"), ("US-MN", "US-SD"),
+    ("US-MN", "US-WI"), ("US-MS", "US-TN"), ("US-MO", "US-NE"), ("US-MO", "US-OK"),
+    ("US-MO", "US-TN"), ("US-MT", "US-ND"), ("US-MT", "US-SD"), ("US-MT", "US-WY"),
+    ("US-NE", "US-SD"), ("US-NE", "US-WY"), ("US-NV", "US-OR"), ("US-NV", "US-UT"),
+    ("US-NH", "US-VT"), ("US-NJ", "US-NY"), ("US-NJ", "US-PA"), ("US-NM", "US-OK"),
+    ("US-NM", "US-TX"), ("US-NM", "US-UT"), ("US-NY", "US-PA"), ("US-NY", "US-VT"),
+    ("US-NC", "US-SC"), ("US-NC", "US-TN"), ("US-NC", "US-VA"), ("US-ND", "US-SD"),
+    ("US-OH", "US-PA"), ("US-OH", "US-WV"), ("US-OK", "US-TX"), ("US-OR", "US-WA"),
+    ("US-PA", "US-WV"), ("US-SD", "US-WY"), ("US-TN", "US-VA"), ("US-UT", "US-WY"),
+    ("US-VA", "US-WV"), ("CA-AB", "CA-BC"), ("CA-AB", "CA-NT"), ("CA-AB", "CA-SK"),
+    ("CA-BC", "CA-NT"), ("CA-BC", "CA-YT"), ("CA-MB", "CA-NU"), ("CA-MB", "CA-ON"),
+    ("CA-MB", "CA-SK"), ("CA-NB", "CA-NS"), ("CA-NB", "CA-QC"), ("CA-NL", "CA-QC"),
+    ("CA-NT", "CA-NU"), ("CA-NT", "CA-SK"), ("CA-NT", "CA-YT"), ("CA-ON", "CA-QC"),
+    ("US-WA", "CA-BC"), ("US-ID", "CA-BC"), ("US-MT", "CA-BC"), ("US-MT", "CA-AB"),
+    ("US-MT", "CA-SK"), ("US-ND", "CA-SK"), ("US-ND", "CA-MB"), ("US-MN", "CA-MB"),
+    ("US-MN", "CA-ON"), ("US-MI", "CA-ON"), ("US-NY", "CA-ON"), ("US-NY", "CA-QC"),
+    ("US-VT", "CA-QC"), ("US-NH", "CA-QC"), ("US-ME", "CA-QC"), ("US-ME", "CA-NB"),
+    ("US-AK", "CA-BC"), ("US-AK", "CA-YT"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionMatch {
+    /// Same region and the same spelling after normalisation.
+    Exact,
+    /// Same region spelled differently (e.g. "CA" vs "California").
+    Alias,
+    /// Regions share a land border.
+    Adjacent,
+    None,
+}
+
+/// Folds case, accents and punctuation, so "N.Y." becomes "n y" and "Québec" becomes "quebec".
+fn region_key(input: &str) -> String {
+    normalize_text(input)
+}
+
+fn region_for_key(key: &str) -> Option<&'static Region> {
+    if key.is_empty() {
+        return None;
+    }
+
+    // Codes are compared without spaces so that "n y" and "ny" both resolve.
+    let compact = key.replace(' ', "");
+    let direct = REGIONS.iter().find(|region| {
+        region.name.eq_ignore_ascii_case(key) || region.code.eq_ignore_ascii_case(&compact)
+    });
+    if direct.is_some() {
+        return direct;
+    }
+
+    let (_, id) = REGION_ALIASES.iter().find(|(alias, _)| *alias == key)?;
+    region_by_id(id)
+}
+
+pub fn region_by_id(id: &str) -> Option<&'static Region> {
+    REGIONS.iter().find(|region| region.id == id)
+}
+
+/// Resolves a free-form state or province string to its region.
+pub fn normalize_region(input: &str) -> Option<&'static Region> {
+    region_for_key(&region_key(input))
+}
+
+pub fn are_adjacent(a: &str, b: &str) -> bool {
+    ADJACENT_REGIONS
+        .iter()
+        .any(|(x, y)| (*x == a && *y == b) || (*x == b && *y == a))
+}
+
+/// Compares two state strings. Unrecognised values only match when their normalised
+/// spelling is identical.
+pub fn match_regions(query: &str, hit: &str) -> RegionMatch {
+    let query_key = region_key(query);
+    let hit_key = region_key(hit);
+    if query_key.is_empty() || hit_key.is_empty() {
+        return RegionMatch::None;
+    }
+
+    match (region_for_key(&query_key), region_for_key(&hit_key)) {
+        (Some(query_region), Some(hit_region)) if query_region.id == hit_region.id => {
+            if query_key == hit_key {
+                RegionMatch::Exact
+            } else {
+                RegionMatch::Alias
+            }
+        }
+        (Some(query_region), Some(hit_region)) if are_adjacent(query_region.id, hit_region.id) => {
+            RegionMatch::Adjacent
+        }
+        _ if query_key == hit_key => RegionMatch::Exact,
+        _ => RegionMatch::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_regions_levels() {
+        assert_eq!(match_regions("New York", "new york"), RegionMatch::Exact);
+        assert_eq!(match_regions("N.Y.", "New York"), RegionMatch::Alias);
+        assert_eq!(match_regions("CA", "California"), RegionMatch::Alias);
+        assert_eq!(match_regions("Oregon", "WA"), RegionMatch::Adjacent);
+        assert_eq!(match_regions("Texas", "Maine"), RegionMatch::None);
+    }
+
+    #[test]
+    fn unknown_regions_only_match_their_own_spelling() {
+        assert_eq!(match_regions("Atlantis", "atlantis"), RegionMatch::Exact);
+        assert_eq!(match_regions("Atlantis", "Lemuria"), RegionMatch::None);
+        assert_eq!(match_regions("", "Texas"), RegionMatch::None);
+    }
+}
@@ -0,0 +1,522 @@
+use crate::error::{from_js, to_js_with_nulls, ScorerError};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use wasm_bindgen::prelude::*;
+
+const USA: &str = "United States of America";
+
+// Minimal, deterministic aliases mirrored from `LOCATION_ALIASES` in `searchPreprocess.ts`.
+// Every alias is in the US; entries with a city enforce an exact city match downstream.
+const LOCATION_ALIASES: &[(&str, Option<&str>, &str)] = &[
+    ("manhattan", Some("New York"), "New York"),
+    ("nyc", None, "New York"),
+    ("new york city", None, "New York"),
+    ("newyorkcity", None, "New York"),
+    ("philadelphia", Some("Philadelphia"), "Pennsylvania"),
+    ("philly", Some("Philadelphia"), "Pennsylvania"),
+    ("phiadelphia", Some("Philadelphia"), "Pennsylvania"),
+    ("brooklyn", Some("Brooklyn"), "New York"),
+    ("boston", Some("Boston"), "Massachusetts"),
+    ("baltimore", Some("Baltimore"), "Maryland"),
+    ("chicago", Some("Chicago"), "Illinois"),
+    ("nashville", Some("Nashville"), "Tennessee"),
+    ("memphis", Some("Memphis"), "Tennessee"),
+    ("washington dc", Some("Washington"), "District of Columbia"),
+    ("washington, dc", Some("Washington"), "District of Columbia"),
+    ("washingtondc", Some("Washington"), "District of Columbia"),
+    (
+        "district of columbia",
+        Some("Washington"),
+        "District of Columbia",
+    ),
+    ("los angeles", Some("Los Angeles"), "California"),
+    ("losangeles", Some("Los Angeles"), "California"),
+    ("las vegas", Some("Las Vegas"), "Nevada"),
+    ("new orleans", Some("New Orleans"), "Louisiana"),
+    ("neworleans", Some("New Orleans"), "Louisiana"),
+    ("san antonio", Some("San Antonio"), "Texas"),
+    ("san diego", Some("San Diego"), "California"),
+    ("san jose", Some("San Jose"), "California"),
+    ("san francisco", Some("San Francisco"), "California"),
+    ("sanfrancisco", Some("San Francisco"), "California"),
+    ("fresno", Some("Fresno"), "California"),
+    ("sacramento", Some("Sacramento"), "California"),
+    ("oakland", Some("Oakland"), "California"),
+    ("long beach", Some("Long Beach"), "California"),
+    ("longbeach", Some("Long Beach"), "California"),
+    ("buffallo", Some("Buffalo"), "New York"),
+    ("rochester", Some("Rochester"), "New York"),
+    ("indianapolis", Some("Indianapolis"), "Indiana"),
+    ("jacksonville", Some("Jacksonville"), "Florida"),
+    ("miami", Some("Miami"), "Florida"),
+    ("houston", Some("Houston"), "Texas"),
+    ("austin", Some("Austin"), "Texas"),
+    ("dallas", Some("Dallas"), "Texas"),
+    ("fort worth", Some("Fort Worth"), "Texas"),
+    ("fortworth", Some("Fort Worth"), "Texas"),
+    ("el paso", Some("El Paso"), "Texas"),
+    ("elpaso", Some("El Paso"), "Texas"),
+    ("atlanta", Some("Atlanta"), "Georgia"),
+    ("louisville", Some("Louisville"), "Kentucky"),
+    ("charlotte", Some("Charlotte"), "North Carolina"),
+    ("raleigh", Some("Raleigh"), "North Carolina"),
+    ("virginia beach", Some("Virginia Beach"), "Virginia"),
+    ("virginiabeach", Some("Virginia Beach"), "Virginia"),
+    ("virginia beah", Some("Virginia Beach"), "Virginia"),
+    ("minneapolis", Some("Minneapolis"), "Minnesota"),
+    ("seattle", Some("Seattle"), "Washington"),
+    ("denver", Some("Denver"), "Colorado"),
+    ("colorado springs", Some("Colorado Springs"), "Colorado"),
+    ("coloradosprings", Some("Colorado Springs"), "Colorado"),
+    ("hartford", Some("Hartford"), "Connecticut"),
+    ("kansas city", Some("Kansas City"), "Missouri"),
+    ("kansascity", Some("Kansas City"), "Missouri"),
+    ("oklahoma city", Some("Oklahoma City"), "Oklahoma"),
+    ("oklahomacity", Some("Oklahoma City"), "Oklahoma"),
+    ("tulsa", Some("Tulsa"), "Oklahoma"),
+    ("detroit", Some("Detroit"), "Michigan"),
+    ("albuquerque", Some("Albuquerque"), "New Mexico"),
+    ("albequerque", Some("Albuquerque"), "New Mexico"),
+    ("milwaukee", Some("Milwaukee"), "Wisconsin"),
+    ("wilmington", Some("Wilmington"), "Delaware"),
+    ("harrisburg", Some("Harrisburg"), "Pennsylvania"),
+    ("omaha", Some("Omaha"), "Nebraska"),
+    ("cleveland", Some("Cleveland"), "Ohio"),
+    ("columbus", Some("Columbus"), "Ohio"),
+    ("wichita", Some("Wichita"), "Kansas"),
+    ("pheonix", Some("Pheonix"), "Arizona"),
+    ("phoenix", Some("Phoenix"), "Arizona"),
+    ("tucson", Some("Tucson"), "Arizona"),
+    ("mesa", Some("Mesa"), "Arizona"),
+];
+
+// Synonyms for state values to ensure strict matching allows common abbreviations.
+const STATE_SYNONYMS: &[(&str, [&str; 2])] = &[
+    ("district of columbia", ["District of Columbia", "DC"]),
+    ("dc", ["District of Columbia", "DC"]),
+    ("new york", ["New York", "NY"]),
+    ("pennsylvania", ["Pennsylvania", "PA"]),
+    ("massachusetts", ["Massachusetts", "MA"]),
+    ("maryland", ["Maryland", "MD"]),
+    ("illinois", ["Illinois", "IL"]),
+    ("alabama", ["Alabama", "AL"]),
+    ("al", ["Alabama", "AL"]),
+    ("alaska", ["Alaska", "AK"]),
+    ("ak", ["Alaska", "AK"]),
+    ("arizona", ["Arizona", "AZ"]),
+    ("az", ["Arizona", "AZ"]),
+    ("arkansas", ["Arkansas", "AR"]),
+    ("ar", ["Arkansas", "AR"]),
+    ("california", ["California", "CA"]),
+    ("ca", ["California", "CA"]),
+    ("colorado", ["Colorado", "CO"]),
+    ("co", ["Colorado", "CO"]),
+    ("connecticut", ["Connecticut", "CT"]),
+    ("ct", ["Connecticut", "CT"]),
+    ("delaware", ["Delaware", "DE"]),
+    ("de", ["Delaware", "DE"]),
+    ("florida", ["Florida", "FL"]),
+    ("fl", ["Florida", "FL"]),
+    ("georgia", ["Georgia", "GA"]),
+    ("ga", ["Georgia", "GA"]),
+    ("hawaii", ["Hawaii", "HI"]),
+    ("hi", ["Hawaii", "HI"]),
+    ("idaho", ["Idaho", "ID"]),
+    ("id", ["Idaho", "ID"]),
+    ("il", ["Illinois", "IL"]),
+    ("indiana", ["Indiana", "IN"]),
+    ("in", ["Indiana", "IN"]),
+    ("iowa", ["Iowa", "IA"]),
+    ("ia", ["Iowa", "IA"]),
+    ("kansas", ["Kansas", "KS"]),
+    ("ks", ["Kansas", "KS"]),
+    ("kentucky", ["Kentucky", "KY"]),
+    ("ky", ["Kentucky", "KY"]),
+    ("louisiana", ["Louisiana", "LA"]),
+    ("la", ["Louisiana", "LA"]),
+    ("maine", ["Maine", "ME"]),
+    ("me", ["Maine", "ME"]),
+    ("md", ["Maryland", "MD"]),
+    ("ma", ["Massachusetts", "MA"]),
+    ("michigan", ["Michigan", "MI"]),
+    ("mi", ["Michigan", "MI"]),
+    ("minnesota", ["Minnesota", "MN"]),
+    ("mn", ["Minnesota", "MN"]),
+    ("mississippi", ["Mississippi", "MS"]),
+    ("ms", ["Mississippi", "MS"]),
+    ("missouri", ["Missouri", "MO"]),
+    ("mo", ["Missouri", "MO"]),
+    ("montana", ["Montana", "MT"]),
+    ("mt", ["Montana", "MT"]),
+    ("nebraska", ["Nebraska", "NE"]),
+    ("ne", ["Nebraska", "NE"]),
+    ("nevada", ["Nevada", "NV"]),
+    ("nv", ["Nevada", "NV"]),
+    ("new hampshire", ["New Hampshire", "NH"]),
+    ("nh", ["New Hampshire", "NH"]),
+    ("new jersey", ["New Jersey", "NJ"]),
+    ("nj", ["New Jersey", "NJ"]),
+    ("new mexico", ["New Mexico", "NM"]),
+    ("nm", ["New Mexico", "NM"]),
+    ("ny", ["New York", "NY"]),
+    ("north carolina", ["North Carolina", "NC"]),
+    ("nc", ["North Carolina", "NC"]),
+    ("north dakota", ["North Dakota", "ND"]),
+    ("nd", ["North Dakota", "ND"]),
+    ("ohio", ["Ohio", "OH"]),
+    ("oh", ["Ohio", "OH"]),
+    ("oklahoma", ["Oklahoma", "OK"]),
+    ("ok", ["Oklahoma", "OK"]),
+    ("oregon", ["Oregon", "OR"]),
+    ("or", ["Oregon", "OR"]),
+    ("pa", ["Pennsylvania", "PA"]),
+    ("rhode island", ["Rhode Island", "RI"]),
+    ("ri", ["Rhode Island", "RI"]),
+    ("south carolina", ["South Carolina", "SC"]),
+    ("sc", ["South Carolina", "SC"]),
+    ("south dakota", ["South Dakota", "SD"]),
+    ("sd", ["South Dakota", "SD"]),
+    ("tennessee", ["Tennessee", "TN"]),
+    ("tn", ["Tennessee", "TN"]),
+    ("texas", ["Texas", "TX"]),
+    ("tx", ["Texas", "TX"]),
+    ("utah", ["Utah", "UT"]),
+    ("ut", ["Utah", "UT"]),
+    ("vermont", ["Vermont", "VT"]),
+    ("vt", ["Vermont", "VT"]),
+    ("virginia", ["Virginia", "VA"]),
+    ("va", ["Virginia", "VA"]),
+    ("washington", ["Washington", "WA"]),
+    ("wa", ["Washington", "WA"]),
+    ("west virginia", ["West Virginia", "WV"]),
+    ("wv", ["West Virginia", "WV"]),
+    ("wisconsin", ["Wisconsin", "WI"]),
+    ("wi", ["Wisconsin", "WI"]),
+    ("wyoming", ["Wyoming", "WY"]),
+    ("wy", ["Wyoming", "WY"]),
+];
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedLocation {
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub rest_of_query: String,
+}
+
+/// Mirrors `LocationOverrideResult` in `searchPreprocess.ts`. `penaltyCities`, `penaltyTerms`
+/// and `strictPenalty` map 1:1 onto `ScoreConfig::{penalty_cities, penalty_terms, strict_penalty}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationOverrideResult {
+    pub overrides: ParsedLocation,
+    pub penalty_cities: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_city_exact_city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_state_any: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_city_any: Option<Vec<String>>,
+    pub penalty_terms: Vec<String>,
+    pub strict_penalty: bool,
+}
+
+struct CompiledPatterns {
+    dc_token: Regex,
+    dc_strip: Regex,
+    la_token: Regex,
+    la_strip: Regex,
+    explicit_louisiana: Regex,
+    whitespace: Regex,
+    aliases: Vec<(usize, Regex)>,
+}
+
+fn patterns() -> &'static CompiledPatterns {
+    static PATTERNS: OnceLock<CompiledPatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| CompiledPatterns {
+        dc_token: Regex::new(r"(?i)(^|[^a-z])d\.?\s*c\.?([^a-z]|$)").unwrap(),
+        dc_strip: Regex::new(r"(?i)\bD\.?\s*C\.?\b").unwrap(),
+        la_token: Regex::new(r"(?i)(^|[^a-z])l\.?\s*a\.?([^a-z]|$)").unwrap(),
+        la_strip: Regex::new(r"(?i)\bL\.?\s*A\.?\b").unwrap(),
+        explicit_louisiana: Regex::new(
+            r"(?i)\blouisiana\b|\bnew orleans\b|\bbaton rouge\b|\bshreveport\b",
+        )
+        .unwrap(),
+        whitespace: Regex::new(r"\s+").unwrap(),
+        aliases: LOCATION_ALIASES
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, (key, ..))| {
+                let pattern = format!("(?i)\\b{}\\b", regex::escape(key));
+                Regex::new(&pattern).ok().map(|regex| (idx, regex))
+            })
+            .collect(),
+    })
+}
+
+fn state_synonyms(state: Option<&str>) -> Option<Vec<String>> {
+    let key = state.unwrap_or_default().to_lowercase();
+    if key.is_empty() {
+        return None;
+    }
+    STATE_SYNONYMS
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, values)| values.iter().map(|v| v.to_string()).collect())
+}
+
+fn strip_token(rest_of_query: &str, token: &Regex, whitespace: &Regex) -> String {
+    if rest_of_query.is_empty() {
+        return String::new();
+    }
+    let removed = token.replace_all(rest_of_query, "");
+    whitespace.replace_all(&removed, " ").trim().to_string()
+}
+
+fn fixed_city_override(
+    parsed: &ParsedLocation,
+    city: &str,
+    state: &str,
+    state_abbr: &str,
+    strip: &Regex,
+) -> LocationOverrideResult {
+    LocationOverrideResult {
+        overrides: ParsedLocation {
+            city: Some(city.to_string()),
+            state: Some(state.to_string()),
+            country: Some(USA.to_string()),
+            rest_of_query: strip_token(&parsed.rest_of_query, strip, &patterns().whitespace),
+        },
+        penalty_cities: Vec::new(),
+        force_city_exact_city: Some(city.to_string()),
+        force_state_any: Some(vec![state.to_string(), state_abbr.to_string()]),
+        force_city_any: None,
+        penalty_terms: Vec::new(),
+        strict_penalty: false,
+    }
+}
+
+/// Rewrites the parsed query location for hardcoded aliases ("philly", "nyc", "D.C.", "LA")
+/// and derives the strict-match hints the search layer uses.
+pub fn apply_hardcoded_location_overrides_impl(
+    raw_query: &str,
+    parsed: ParsedLocation,
+) -> LocationOverrideResult {
+    let patterns = patterns();
+    let lowered = raw_query.trim().to_lowercase();
+
+    // Standalone "DC" tokens (e.g. "Music venues DC", "in D.C.") map to Washington, DC.
+    if patterns.dc_token.is_match(&lowered) {
+        return fixed_city_override(
+            &parsed,
+            "Washington",
+            "District of Columbia",
+            "DC",
+            &patterns.dc_strip,
+        );
+    }
+
+    // Standalone "LA" tokens likely mean Los Angeles unless Louisiana is explicitly referenced.
+    if patterns.la_token.is_match(&lowered) && !patterns.explicit_louisiana.is_match(&lowered) {
+        return fixed_city_override(
+            &parsed,
+            "Los Angeles",
+            "California",
+            "CA",
+            &patterns.la_strip,
+        );
+    }
+
+    // Prefer the longest matching alias (e.g. "washington dc" over "dc"); ties keep table order.
+    let mut hit: Option<(usize, &Regex)> = None;
+    for (idx, regex) in &patterns.aliases {
+        if !regex.is_match(&lowered) {
+            continue;
+        }
+        let longer = hit.is_none_or(|(best, _)| {
+            LOCATION_ALIASES[*idx].0.len() > LOCATION_ALIASES[best].0.len()
+        });
+        if longer {
+            hit = Some((*idx, regex));
+        }
+    }
+
+    let Some((alias_idx, alias_regex)) = hit else {
+        // No alias: still allow common synonyms for a parsed state (California <-> CA).
+        let force_state_any = state_synonyms(parsed.state.as_deref());
+        let force_city_exact_city = parsed.city.clone();
+        return LocationOverrideResult {
+            overrides: parsed,
+            penalty_cities: Vec::new(),
+            force_city_exact_city,
+            force_state_any,
+            force_city_any: None,
+            penalty_terms: Vec::new(),
+            strict_penalty: false,
+        };
+    };
+
+    let (key, alias_city, alias_state) = LOCATION_ALIASES[alias_idx];
+    let city = alias_city.map(str::to_string).or(parsed.city);
+    let state = Some(alias_state.to_string());
+    let country = Some(USA.to_string());
+
+    let force_city_exact_city = alias_city.and(city.clone());
+    let force_state_any = state_synonyms(state.as_deref());
+    // NYC and "New York City": allow both New York and Brooklyn as exact city matches.
+    let force_city_any = matches!(key, "nyc" | "new york city" | "newyorkcity")
+        .then(|| vec!["New York".to_string(), "Brooklyn".to_string()]);
+
+    LocationOverrideResult {
+        overrides: ParsedLocation {
+            city,
+            state,
+            country,
+            rest_of_query: strip_token(&parsed.rest_of_query, alias_regex, &patterns.whitespace),
+        },
+        penalty_cities: Vec::new(),
+        force_city_exact_city,
+        force_state_any,
+        force_city_any,
+        penalty_terms: Vec::new(),
+        strict_penalty: false,
+    }
+}
+
+#[wasm_bindgen]
+pub fn apply_hardcoded_location_overrides(
+    raw_query: &str,
+    parsed: JsValue,
+) -> Result<JsValue, ScorerError> {
+    let parsed: ParsedLocation = from_js("parsed location", parsed)?;
+
+    let result = apply_hardcoded_location_overrides_impl(raw_query, parsed);
+    to_js_with_nulls("location overrides", &result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(city: Option<&str>, state: Option<&str>, rest_of_query: &str) -> ParsedLocation {
+        ParsedLocation {
+            city: city.map(str::to_string),
+            state: state.map(str::to_string),
+            country: None,
+            rest_of_query: rest_of_query.to_string(),
+        }
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn dc_tokens_map_to_washington() {
+        for query in ["Music venues DC", "jazz bars in D.C.", "wedding venues d c"] {
+            let result = apply_hardcoded_location_overrides_impl(query, parsed(None, None, query));
+            assert_eq!(
+                result.overrides.city.as_deref(),
+                Some("Washington"),
+                "{query}"
+            );
+            assert_eq!(
+                result.overrides.state.as_deref(),
+                Some("District of Columbia")
+            );
+            assert_eq!(result.overrides.country.as_deref(), Some(USA));
+            assert_eq!(result.force_city_exact_city.as_deref(), Some("Washington"));
+            assert_eq!(
+                result.force_state_any,
+                Some(strings(&["District of Columbia", "DC"]))
+            );
+            assert_eq!(result.force_city_any, None);
+        }
+
+        let result = apply_hardcoded_location_overrides_impl(
+            "Music venues DC",
+            parsed(None, None, "Music venues DC"),
+        );
+        assert_eq!(result.overrides.rest_of_query, "Music venues");
+    }
+
+    #[test]
+    fn la_tokens_map_to_los_angeles_unless_louisiana_is_named() {
+        let result = apply_hardcoded_location_overrides_impl(
+            "coffee shops LA",
+            parsed(None, Some("Louisiana"), "coffee shops LA"),
+        );
+        assert_eq!(result.overrides.city.as_deref(), Some("Los Angeles"));
+        assert_eq!(result.overrides.state.as_deref(), Some("California"));
+        assert_eq!(result.overrides.rest_of_query, "coffee shops");
+        assert_eq!(result.force_city_exact_city.as_deref(), Some("Los Angeles"));
+        assert_eq!(result.force_state_any, Some(strings(&["California", "CA"])));
+
+        let result = apply_hardcoded_location_overrides_impl(
+            "bars la new orleans",
+            parsed(None, None, "bars la new orleans"),
+        );
+        assert_eq!(result.overrides.city.as_deref(), Some("New Orleans"));
+        assert_eq!(result.overrides.state.as_deref(), Some("Louisiana"));
+        assert_eq!(result.overrides.rest_of_query, "bars la");
+    }
+
+    #[test]
+    fn longest_matching_alias_wins() {
+        let query = "venues in manhattan or brooklyn";
+        let result = apply_hardcoded_location_overrides_impl(query, parsed(None, None, query));
+        assert_eq!(result.overrides.city.as_deref(), Some("New York"));
+        assert_eq!(result.overrides.state.as_deref(), Some("New York"));
+        assert_eq!(result.overrides.rest_of_query, "venues in or brooklyn");
+        assert_eq!(result.force_city_exact_city.as_deref(), Some("New York"));
+        assert_eq!(result.force_state_any, Some(strings(&["New York", "NY"])));
+        assert_eq!(result.force_city_any, None);
+
+        let query = "rooftop bars new york city manhattan";
+        let result = apply_hardcoded_location_overrides_impl(query, parsed(None, None, query));
+        assert_eq!(result.overrides.rest_of_query, "rooftop bars manhattan");
+        assert_eq!(result.force_city_exact_city, None);
+    }
+
+    #[test]
+    fn nyc_aliases_allow_new_york_and_brooklyn() {
+        for query in [
+            "jazz clubs nyc",
+            "jazz clubs new york city",
+            "jazz clubs newyorkcity",
+        ] {
+            let result = apply_hardcoded_location_overrides_impl(
+                query,
+                parsed(None, Some("New York"), "jazz clubs"),
+            );
+            assert_eq!(result.overrides.city, None, "{query}");
+            assert_eq!(result.overrides.state.as_deref(), Some("New York"));
+            assert_eq!(result.overrides.country.as_deref(), Some(USA));
+            assert_eq!(result.force_city_exact_city, None);
+            assert_eq!(result.force_state_any, Some(strings(&["New York", "NY"])));
+            assert_eq!(
+                result.force_city_any,
+                Some(strings(&["New York", "Brooklyn"]))
+            );
+        }
+    }
+
+    #[test]
+    fn queries_without_an_alias_keep_the_parsed_location() {
+        let result = apply_hardcoded_location_overrides_impl(
+            "breweries in modesto",
+            parsed(Some("Modesto"), Some("California"), "breweries"),
+        );
+        assert_eq!(result.overrides.city.as_deref(), Some("Modesto"));
+        assert_eq!(result.overrides.rest_of_query, "breweries");
+        assert_eq!(result.force_city_exact_city.as_deref(), Some("Modesto"));
+        assert_eq!(result.force_state_any, Some(strings(&["California", "CA"])));
+        assert_eq!(result.force_city_any, None);
+    }
+}
@@ -0,0 +1,192 @@
+//! Compiled term matchers for post-training profiles.
+//!
+//! Terms match whole words by default, so "ent" no longer hits "entertainment". A profile can
+//! set `termMatch: "substring"` to go back to the old `includes` check, and a term can opt into
+//! another mode with a small syntax that survives JSON profiles unchanged:
+//!
+//! - `"booking agent"` matches the quoted phrase at word boundaries, even when the profile
+//!   default is `substring`
+//! - `music*` matches any word that starts with "music"
+//! - `*tix*` matches anywhere, like the old `includes` check
+//! - `/dj|disc jockey/` is a regular expression, run case-insensitively against normalised text
+//!
+//! Everything except regex terms goes through [`normalize_text`] first, and the text being
+//! matched is normalised by the caller, so punctuation in a pattern has already become a space.
+//! A substring term that lost punctuation that way must end at a word boundary, so
+//! `wedding.com` still hits "wedding.com/vendors" but not "Wedding Company".
+
+use crate::text::normalize_text;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use unicode_normalization::char::is_combining_mark;
+
+/// How a term without explicit syntax is matched. Set per profile via `termMatch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermMode {
+    #[default]
+    Word,
+    Phrase,
+    Prefix,
+    Substring,
+    Regex,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Substring(String),
+    Pattern(Regex),
+}
+
+#[derive(Debug, Clone)]
+pub struct TermMatcher {
+    /// Normalised term (or the raw pattern for regex terms), for reporting matches.
+    pub term: String,
+    pub mode: TermMode,
+    matcher: Matcher,
+}
+
+/// `(^|\b)term(\b|$)` over an already-normalised term.
+pub fn word_boundary_regex(term: &str) -> Option<Regex> {
+    Regex::new(&format!("(^|\\b){}(\\b|$)", regex::escape(term))).ok()
+}
+
+fn prefix_regex(term: &str) -> Option<Regex> {
+    Regex::new(&format!("(^|\\b){}", regex::escape(term))).ok()
+}
+
+fn suffix_regex(term: &str) -> Option<Regex> {
+    Regex::new(&format!("{}(\\b|$)", regex::escape(term))).ok()
+}
+
+/// Whether normalising `raw` turned punctuation into spaces, e.g. in "wedding.com" or "d.j.".
+fn has_punctuation(raw: &str) -> bool {
+    raw.chars()
+        .any(|c| !c.is_alphanumeric() && !c.is_whitespace() && !is_combining_mark(c))
+}
+
+fn strip_wrapping(raw: &str, open: char, close: char) -> Option<&str> {
+    let inner = raw.strip_prefix(open)?.strip_suffix(close)?;
+    (!inner.is_empty()).then_some(inner)
+}
+
+fn split_mode(raw: &str, default_mode: TermMode) -> (TermMode, &str) {
+    if let Some(inner) = strip_wrapping(raw, '/', '/') {
+        return (TermMode::Regex, inner);
+    }
+    if let Some(inner) = strip_wrapping(raw, '"', '"') {
+        return (TermMode::Phrase, inner);
+    }
+    if let Some(inner) = strip_wrapping(raw, '*', '*') {
+        return (TermMode::Substring, inner);
+    }
+    if let Some(inner) = raw.strip_suffix('*').filter(|inner| !inner.is_empty()) {
+        return (TermMode::Prefix, inner);
+    }
+    (default_mode, raw)
+}
+
+impl TermMatcher {
+    /// Compiles one profile term. Returns `None` for terms that normalise to nothing and for
+    /// invalid regular expressions, which are skipped rather than failing the whole profile.
+    pub fn parse(raw: &str, default_mode: TermMode) -> Option<Self> {
+        let (mode, body) = split_mode(raw.trim(), default_mode);
+
+        if mode == TermMode::Regex {
+            let regex = RegexBuilder::new(body)
+                .case_insensitive(true)
+                .build()
+                .ok()?;
+            return Some(Self {
+                term: body.to_string(),
+                mode,
+                matcher: Matcher::Pattern(regex),
+            });
+        }
+
+        let term = normalize_text(body);
+        if term.is_empty() {
+            return None;
+        }
+        let matcher = match mode {
+            TermMode::Substring if has_punctuation(body) => Matcher::Pattern(suffix_regex(&term)?),
+            TermMode::Substring => Matcher::Substring(term.clone()),
+            TermMode::Prefix => Matcher::Pattern(prefix_regex(&term)?),
+            TermMode::Word | TermMode::Phrase | TermMode::Regex => {
+                Matcher::Pattern(word_boundary_regex(&term)?)
+            }
+        };
+        Some(Self {
+            term,
+            mode,
+            matcher,
+        })
+    }
+
+    /// `text` must already be normalised with [`normalize_text`].
+    pub fn is_match(&self, text: &str) -> bool {
+        if text.is_empty() {
+            return false;
+        }
+        match &self.matcher {
+            Matcher::Substring(term) => text.contains(term.as_str()),
+            Matcher::Pattern(regex) => regex.is_match(text),
+        }
+    }
+}
+
+pub fn compile_terms(terms: &[String], default_mode: TermMode) -> Vec<TermMatcher> {
+    terms
+        .iter()
+        .filter_map(|term| TermMatcher::parse(term, default_mode))
+        .collect()
+}
+
+pub fn matches_any(text: &str, terms: &[TermMatcher]) -> bool {
+    !text.is_empty() && terms.iter().any(|term| term.is_match(text))
+}
+
+/// Number of distinct terms in `terms` that match `text`.
+pub fn count_matches(text: &str, terms: &[TermMatcher]) -> usize {
+    if text.is_empty() {
+        return 0;
+    }
+    terms.iter().filter(|term| term.is_match(text)).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(raw: &str, default_mode: TermMode, text: &str) -> bool {
+        let matcher = TermMatcher::parse(raw, default_mode).unwrap();
+        matcher.is_match(&normalize_text(text))
+    }
+
+    #[test]
+    fn default_modes() {
+        assert!(is_match("venue", TermMode::Substring, "Venues"));
+        assert!(is_match("ent", TermMode::Substring, "Entertainment"));
+        assert!(!is_match("ent", TermMode::Word, "Entertainment"));
+        assert!(is_match("ent", TermMode::Word, "ENT specialist"));
+    }
+
+    #[test]
+    fn term_syntax_overrides_the_default() {
+        let word = TermMode::Word;
+        assert!(is_match("\"booking agent\"", word, "Senior Booking-Agent"));
+        assert!(!is_match("\"booking agent\"", word, "booking agents"));
+        assert!(is_match("music*", word, "Musicians Guild"));
+        assert!(!is_match("music*", word, "Amusical"));
+        assert!(is_match("*tix*", word, "BigTix Inc"));
+        assert!(is_match("/dj|disc jockey/", word, "Club DJ"));
+        let regex = TermMatcher::parse("/dj/", word).unwrap();
+        assert_eq!(regex.mode, TermMode::Regex);
+    }
+
+    #[test]
+    fn unusable_terms_are_skipped() {
+        assert!(TermMatcher::parse("  --  ", TermMode::Word).is_none());
+        assert!(TermMatcher::parse("/(unclosed/", TermMode::Word).is_none());
+    }
+}
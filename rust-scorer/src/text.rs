@@ -0,0 +1,83 @@
+//! Shared text normalisation for every matcher in the crate.
+//!
+//! Applies Unicode compatibility normalisation (so full-width and ligature forms match their
+//! ASCII equivalents), strips diacritics, lowercases, and collapses runs of whitespace and
+//! punctuation into a single space. "Café  Nöir!" and "cafe noir" both become "cafe noir".
+//! [`website_domain`] does the same job for website URLs.
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Letters with no Unicode decomposition that still have a common ASCII spelling.
+fn fold_letter(c: char, out: &mut String) {
+    match c {
+        'ß' => out.push_str("ss"),
+        'æ' => out.push_str("ae"),
+        'œ' => out.push_str("oe"),
+        'ø' => out.push('o'),
+        'ł' => out.push('l'),
+        'đ' | 'ð' => out.push('d'),
+        'þ' => out.push_str("th"),
+        'ı' => out.push('i'),
+        _ => out.push(c),
+    }
+}
+
+pub fn normalize_text(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut pending_space = false;
+
+    let folded = input.nfkd().filter(|c| !is_combining_mark(*c)).nfc();
+    for c in folded {
+        if !c.is_alphanumeric() {
+            pending_space = !out.is_empty();
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        for lower in c.to_lowercase() {
+            fold_letter(lower, &mut out);
+        }
+    }
+
+    out
+}
+
+/// Hosts shared by unrelated businesses, which must not be treated as one business.
+const SHARED_WEBSITE_HOSTS: &[&str] = &[
+    "facebook.com",
+    "m.facebook.com",
+    "instagram.com",
+    "linktr.ee",
+    "linkedin.com",
+    "twitter.com",
+    "x.com",
+    "youtube.com",
+    "tiktok.com",
+    "yelp.com",
+    "google.com",
+    "sites.google.com",
+    "eventbrite.com",
+    "soundcloud.com",
+];
+
+/// Lowercased website host without scheme, `www.`, port or path. `None` for hosts that do not
+/// identify a single business.
+pub fn website_domain(website: &str) -> Option<String> {
+    let lower = website.trim().to_lowercase();
+    let without_scheme = lower
+        .split_once("://")
+        .map_or(lower.as_str(), |(_, rest)| rest);
+    let authority = without_scheme.split(['/', '?', '#']).next()?;
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?.trim_end_matches('.');
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    if host.is_empty() || !host.contains('.') || SHARED_WEBSITE_HOSTS.contains(&host) {
+        return None;
+    }
+    Some(host.to_string())
+}
@@ -17,8 +17,12 @@ export type PostTrainingProfile = {
 	auxTitleTerms?: string[];
 	auxWebsiteTerms?: string[];
 	auxIndustryTerms?: string[];
+	// How terms without explicit syntax match; whole words unless set.
+	termMatch?: PostTrainingTermMatch;
 };
 
+export type PostTrainingTermMatch = 'word' | 'phrase' | 'prefix' | 'substring' | 'regex';
+
 const VALID_QUERY_TYPES = [
 	'music_venue',
 	'wedding_planner',
@@ -1,4 +1,7 @@
-import type { PostTrainingProfile } from '@/app/api/_utils/postTraining';
+import type {
+	PostTrainingProfile,
+	PostTrainingTermMatch,
+} from '@/app/api/_utils/postTraining';
 
 declare const __non_webpack_require__: NodeRequire | undefined;
 
@@ -8,26 +11,82 @@ export type VectorEsMatch = {
 	metadata?: Record<string, unknown> | null;
 };
 
+type TermMatcher = (lowercasedText: string) => boolean;
+
 type PostTrainingTerms = {
-	excludeTerms: string[];
-	demoteTerms: string[];
-	includeCompanyTerms: string[];
-	includeTitleTerms: string[];
-	includeWebsiteTerms: string[];
-	includeIndustryTerms: string[];
-	includeCompanyOrTitleTerms: string[];
-	auxCompanyTerms: string[];
-	auxTitleTerms: string[];
-	auxWebsiteTerms: string[];
-	auxIndustryTerms: string[];
-	auxCompanyOrTitleTerms: string[];
+	excludeTerms: TermMatcher[];
+	demoteTerms: TermMatcher[];
+	includeCompanyTerms: TermMatcher[];
+	includeTitleTerms: TermMatcher[];
+	includeWebsiteTerms: TermMatcher[];
+	includeIndustryTerms: TermMatcher[];
+	includeCompanyOrTitleTerms: TermMatcher[];
+	auxCompanyTerms: TermMatcher[];
+	auxTitleTerms: TermMatcher[];
+	auxWebsiteTerms: TermMatcher[];
+	auxIndustryTerms: TermMatcher[];
+	auxCompanyOrTitleTerms: TermMatcher[];
 	requirePositive: boolean;
 };
 
-const normalizeTerms = (terms: string[] | undefined): string[] =>
+const escapeRegExp = (value: string): string =>
+	value.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+
+// A letter or digit on either side means the term is part of a longer word.
+const wordRegExp = (term: string, matchWordEnd: boolean): RegExp =>
+	new RegExp(
+		`(?<![\\p{L}\\p{N}])${escapeRegExp(term)}${matchWordEnd ? '(?![\\p{L}\\p{N}])' : ''}`,
+		'u'
+	);
+
+const unwrap = (raw: string, open: string, close: string): string | null =>
+	raw.length > 2 && raw.startsWith(open) && raw.endsWith(close) ? raw.slice(1, -1) : null;
+
+// Same syntax as the WASM `terms` module: "phrase", prefix*, *substring* and
+// /regex/, with bare terms matched according to `termMatch`.
+const compileTerm = (
+	raw: string,
+	defaultMode: PostTrainingTermMatch
+): TermMatcher | null => {
+	const trimmed = raw.trim();
+	const regexBody = unwrap(trimmed, '/', '/');
+	const phrase = unwrap(trimmed, '"', '"');
+	const substring = unwrap(trimmed, '*', '*');
+	const prefix =
+		trimmed.length > 1 && trimmed.endsWith('*') ? trimmed.slice(0, -1) : null;
+	const [mode, body]: [PostTrainingTermMatch, string] =
+		regexBody != null
+			? ['regex', regexBody]
+			: phrase != null
+				? ['phrase', phrase]
+				: substring != null
+					? ['substring', substring]
+					: prefix != null
+						? ['prefix', prefix]
+						: [defaultMode, trimmed];
+
+	if (mode === 'regex') {
+		try {
+			const regex = new RegExp(body, 'iu');
+			return (text) => regex.test(text);
+		} catch {
+			return null;
+		}
+	}
+	const term = body.toLowerCase().trim();
+	if (!term) return null;
+	if (mode === 'substring') return (text) => text.includes(term);
+	const regex = wordRegExp(term, mode !== 'prefix');
+	return (text) => regex.test(text);
+};
+
+const compileTerms = (
+	terms: string[] | undefined,
+	defaultMode: PostTrainingTermMatch
+): TermMatcher[] =>
 	(terms ?? [])
-		.map((term) => term.toLowerCase().trim())
-		.filter(Boolean);
+		.map((term) => compileTerm(term, defaultMode))
+		.filter((matcher): matcher is TermMatcher => matcher != null);
 
 const metadataValue = (
 	metadata: Record<string, unknown> | null | undefined,
@@ -44,30 +103,31 @@ const metadataValue = (
 	return String(value);
 };
 
-const containsAny = (text: unknown, terms: string[]): boolean => {
+const containsAny = (text: unknown, terms: TermMatcher[]): boolean => {
 	if (text == null || terms.length === 0) return false;
 	const lc = String(text).toLowerCase();
-	return terms.some((term) => term.length > 0 && lc.includes(term));
+	return terms.some((matches) => matches(lc));
 };
 
 const buildPostTrainingTerms = (profile: PostTrainingProfile): PostTrainingTerms => {
-	const includeCompanyTerms = normalizeTerms(profile.includeCompanyTerms);
-	const includeTitleTerms = normalizeTerms(profile.includeTitleTerms);
-	const auxCompanyTerms = normalizeTerms(profile.auxCompanyTerms);
-	const auxTitleTerms = normalizeTerms(profile.auxTitleTerms);
+	const mode = profile.termMatch ?? 'word';
+	const includeCompanyTerms = compileTerms(profile.includeCompanyTerms, mode);
+	const includeTitleTerms = compileTerms(profile.includeTitleTerms, mode);
+	const auxCompanyTerms = compileTerms(profile.auxCompanyTerms, mode);
+	const auxTitleTerms = compileTerms(profile.auxTitleTerms, mode);
 
 	return {
-		excludeTerms: normalizeTerms(profile.excludeTerms),
-		demoteTerms: normalizeTerms(profile.demoteTerms),
+		excludeTerms: compileTerms(profile.excludeTerms, mode),
+		demoteTerms: compileTerms(profile.demoteTerms, mode),
 		includeCompanyTerms,
 		includeTitleTerms,
-		includeWebsiteTerms: normalizeTerms(profile.includeWebsiteTerms),
-		includeIndustryTerms: normalizeTerms(profile.includeIndustryTerms),
+		includeWebsiteTerms: compileTerms(profile.includeWebsiteTerms, mode),
+		includeIndustryTerms: compileTerms(profile.includeIndustryTerms, mode),
 		includeCompanyOrTitleTerms: [...includeCompanyTerms, ...includeTitleTerms],
 		auxCompanyTerms,
 		auxTitleTerms,
-		auxWebsiteTerms: normalizeTerms(profile.auxWebsiteTerms),
-		auxIndustryTerms: normalizeTerms(profile.auxIndustryTerms),
+		auxWebsiteTerms: compileTerms(profile.auxWebsiteTerms, mode),
+		auxIndustryTerms: compileTerms(profile.auxIndustryTerms, mode),
 		auxCompanyOrTitleTerms: [...auxCompanyTerms, ...auxTitleTerms],
 		requirePositive: Boolean(profile.requirePositive),
 	};
@@ -122,18 +182,36 @@ export const applyPostTrainingToEsMatchesTs = (
 	const terms = buildPostTrainingTerms(profile);
 
 	// Keep hard excludes at the ES stage so hydration and downstream ranking
-	// only process relevant candidates.
+	// only process relevant candidates. strictExclude also checks website and
+	// industry, matching the WASM implementation.
+	const strictExclude = Boolean(profile.strictExclude);
 	const strictlyAllowed = matches.filter((match) => {
 		const metadata = match.metadata ?? {};
 		return !(
 			containsAny(metadataValue(metadata, 'company'), terms.excludeTerms) ||
 			containsAny(metadataValue(metadata, 'title'), terms.excludeTerms) ||
-			containsAny(metadataValue(metadata, 'headline'), terms.excludeTerms)
+			containsAny(metadataValue(metadata, 'headline'), terms.excludeTerms) ||
+			(strictExclude &&
+				(containsAny(metadataValue(metadata, 'website'), terms.excludeTerms) ||
+					containsAny(metadataValue(metadata, 'companyIndustry'), terms.excludeTerms)))
 		);
 	});
 
+	const keyOf = (match: VectorEsMatch): string =>
+		metadataValue(match.metadata, 'contactId') || String(match.id || '');
+
 	if (!terms.requirePositive) {
-		return strictlyAllowed.slice(0, finalLimit);
+		// Same contact dedupe as the WASM path; matches without a key are kept.
+		const seenKeys = new Set<string>();
+		return strictlyAllowed
+			.filter((match) => {
+				const key = keyOf(match);
+				if (!key) return true;
+				if (seenKeys.has(key)) return false;
+				seenKeys.add(key);
+				return true;
+			})
+			.slice(0, finalLimit);
 	}
 
 	const classified = strictlyAllowed.map((match) => {
@@ -148,8 +226,6 @@ export const applyPostTrainingToEsMatchesTs = (
 	});
 
 	const seen = new Set<string>();
-	const keyOf = (match: VectorEsMatch): string =>
-		metadataValue(match.metadata, 'contactId') || String(match.id || '');
 	const ordered: VectorEsMatch[] = [];
 	const pushIfNew = (match: VectorEsMatch) => {
 		const key = keyOf(match);